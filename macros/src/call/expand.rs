@@ -3,7 +3,7 @@ use quote::quote;
 
 // See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
-	let CallDef { pallet_struct, methods } = def;
+	let CallDef { pallet_struct, methods, currency_type } = def;
 
 	// This is a vector of all the callable function names.
 	let fn_name = methods.iter().map(|method| &method.name).collect::<Vec<_>>();
@@ -23,8 +23,75 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 		.map(|method| method.args.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>())
 		.collect::<Vec<_>>();
 
+	// The currency passed to each function which takes it, right after the `caller`.
+	let currency_arg = methods
+		.iter()
+		.map(|method| if method.currency { quote!(currency,) } else { quote!() })
+		.collect::<Vec<_>>();
+
+	// The names of the functions and their arguments as strings, used to look up their docs.
+	let fn_name_str = fn_name.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+	let args_name_str = args_name
+		.iter()
+		.map(|args| args.iter().map(|name| name.to_string()).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+
+	// The doc comment of each function, and the doc of each of its arguments. Missing docs are
+	// empty strings.
+	let fn_docs = methods.iter().map(|method| &method.docs).collect::<Vec<_>>();
+	let args_docs = methods.iter().map(|method| &method.args_docs).collect::<Vec<_>>();
+
+	// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
+	// appropriate function call with all arguments, including the `caller`. Pallets whose functions
+	// take a currency are dispatched with the currency the runtime passes in.
+	let dispatch_trait_impl = match currency_type {
+		Some(currency_type) => quote! {
+			impl<T: Config> crate::support::DispatchWithCurrency<#currency_type> for #pallet_struct<T> {
+				type Caller = T::AccountId;
+				type Call = Call<T>;
+
+				fn dispatch(
+					&mut self,
+					currency: &mut #currency_type,
+					caller: Self::Caller,
+					call: Self::Call,
+				) -> crate::support::DispatchResult {
+					match call {
+						#(
+							Call::#fn_name { #( #args_name ),* } => {
+								self.#fn_name(caller, #currency_arg #( #args_name ),*)?;
+							},
+						)*
+					}
+					Ok(())
+				}
+			}
+		},
+		None => quote! {
+			impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
+				type Caller = T::AccountId;
+				type Call = Call<T>;
+
+				fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> crate::support::DispatchResult {
+					match call {
+						#(
+							Call::#fn_name { #( #args_name ),* } => {
+								self.#fn_name(
+									// Note that we assume the first argument of every call is the `caller`.
+									caller,
+									#( #args_name ),*
+								)?;
+							},
+						)*
+					}
+					Ok(())
+				}
+			}
+		},
+	};
+
 	// This quote block creates an `enum Call` which contains all the calls exposed by our pallet,
-	// and the `Dispatch` trait logic to route a `caller` to access those functions.
+	// and the dispatch logic to route a `caller` to access those functions.
 	let dispatch_impl = quote! {
 		// The callable functions exposed by this pallet.
 
@@ -36,27 +103,28 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 			)*
 		}
 
-		// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
-		// appropriate function call with all arguments, including the `caller`.
-		impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
-			type Caller = T::AccountId;
-			type Call = Call<T>;
+		// Documentation of the callable functions, captured from their doc comments.
+		impl<T: Config> Call<T> {
+			// Returns the doc comment of every callable function as `(call name, doc)`.
+			#[allow(dead_code)]
+			pub fn docs() -> &'static [(&'static str, &'static str)] {
+				&[ #( (#fn_name_str, #fn_docs) ),* ]
+			}
 
-			fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> crate::support::DispatchResult {
+			// Returns the doc of every argument of the call named `call` as `(arg name, doc)`.
+			// Unknown calls have no arguments.
+			#[allow(dead_code)]
+			pub fn args_docs(call: &str) -> &'static [(&'static str, &'static str)] {
 				match call {
 					#(
-						Call::#fn_name { #( #args_name ),* } => {
-							self.#fn_name(
-								// Note that we assume the first argument of every call is the `caller`.
-								caller,
-								#( #args_name ),*
-							)?;
-						},
+						#fn_name_str => &[ #( (#args_name_str, #args_docs) ),* ],
 					)*
+					_ => &[],
 				}
-				Ok(())
 			}
 		}
+
+		#dispatch_trait_impl
 	};
 
 	// Return the generated code.
@@ -16,6 +16,8 @@ pub struct CallDef {
 	pub pallet_struct: syn::Ident,
 	// This is a list of the callable functions exposed by this pallet. See `CallVariantDef`.
 	pub methods: Vec<CallVariantDef>,
+	// The currency type of the `currency: &mut Currency` argument, if any function takes one.
+	pub currency_type: Option<Box<syn::Type>>,
 }
 
 // This is the metadata we keep about each callable function in our pallet.
@@ -25,6 +27,12 @@ pub struct CallVariantDef {
 	pub name: syn::Ident,
 	// Information on args of the function: `(name, type)`.
 	pub args: Vec<(syn::Ident, Box<syn::Type>)>,
+	// Whether the function takes the `currency: &mut Currency` argument right after the `caller`.
+	pub currency: bool,
+	// The doc comment on the function, or an empty string if there is none.
+	pub docs: String,
+	// The doc for each arg in `args`, in the same order. Empty when the arg is not documented.
+	pub args_docs: Vec<String>,
 }
 
 impl CallDef {
@@ -45,6 +53,7 @@ impl CallDef {
 
 		// Here is where we will store all the callable functions.
 		let mut methods = vec![];
+		let mut currency_type = None;
 		for item in item_impl.items {
 			if let syn::ImplItem::Fn(method) = item {
 				// Here is where we will store all the args for each callable functions.
@@ -74,8 +83,22 @@ impl CallDef {
 
 				let fn_name = method.sig.ident.clone();
 
-				// Parsing the rest of the args. Skipping 2 for `self` and `caller`.
-				for arg in method.sig.inputs.iter().skip(2) {
+				// The third argument can be the currency the runtime passes in, which is not part
+				// of the call itself.
+				let currency = match method.sig.inputs.iter().nth(2) {
+					Some(syn::FnArg::Typed(arg)) => match check_currency_arg(arg) {
+						Some(type_) => {
+							currency_type.get_or_insert(type_);
+							true
+						},
+						None => false,
+					},
+					_ => false,
+				};
+
+				// Parsing the rest of the args. Skipping `self`, `caller` and the currency.
+				let skip = if currency { 3 } else { 2 };
+				for arg in method.sig.inputs.iter().skip(skip) {
 					// All arguments should be typed.
 					let arg = if let syn::FnArg::Typed(arg) = arg {
 						arg
@@ -95,13 +118,59 @@ impl CallDef {
 					args.push((arg_ident, arg.ty.clone()));
 				}
 
+				// Collect the doc comment of the function, and the docs of each argument from it.
+				let docs = extract_docs(&method.attrs);
+				let args_docs = args.iter().map(|(name, _)| extract_arg_doc(&docs, name)).collect();
+
 				// Store all the function name and the arg data for the function.
-				methods.push(CallVariantDef { name: fn_name, args });
+				methods.push(CallVariantDef { name: fn_name, args, currency, docs, args_docs });
 			}
 		}
 
 		// Return all callable functions for this pallet.
-		Ok(Self { pallet_struct, methods })
+		Ok(Self { pallet_struct, methods, currency_type })
+	}
+}
+
+// Collect all the `///` doc comment lines of an item into a single string.
+// Doc comments are turned into `#[doc = "..."]` attributes by the compiler, so we read those.
+pub fn extract_docs(attrs: &[syn::Attribute]) -> String {
+	let mut lines = vec![];
+	for attr in attrs {
+		if !attr.path().is_ident("doc") {
+			continue
+		}
+		if let syn::Meta::NameValue(meta) = &attr.meta {
+			if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) = &meta.value {
+				let line = lit.value();
+				// `/// text` becomes `" text"`, so we strip that single leading space.
+				lines.push(line.strip_prefix(' ').unwrap_or(&line).to_string());
+			}
+		}
+	}
+	lines.join("\n").trim().to_string()
+}
+
+// Rust does not allow doc comments on function parameters, so arguments are documented in the
+// function doc comment with one line per argument in the form: "- `name`: description".
+pub fn extract_arg_doc(docs: &str, name: &syn::Ident) -> String {
+	let prefix = format!("- `{}`:", name);
+	docs.lines()
+		.find_map(|line| line.trim().strip_prefix(&prefix))
+		.map(|doc| doc.trim().to_string())
+		.unwrap_or_default()
+}
+
+// Check if an arg is `currency: &mut Currency` (or `_currency`), and return the `Currency` type.
+pub fn check_currency_arg(arg: &syn::PatType) -> Option<Box<syn::Type>> {
+	let syn::Pat::Ident(ident) = &*arg.pat else { return None };
+	if &ident.ident != "currency" && &ident.ident != "_currency" {
+		return None
+	}
+	match &*arg.ty {
+		syn::Type::Reference(reference) if reference.mutability.is_some() =>
+			Some(reference.elem.clone()),
+		_ => None,
 	}
 }
 
@@ -3,25 +3,67 @@ use quote::quote;
 
 // See the `fn runtime` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
-	let RuntimeDef { runtime_struct, pallets } = def;
+	let RuntimeDef { runtime_struct, pallets, currencies } = def;
 
 	// This is a vector of all the pallet names, not including system.
 	let pallet_names = pallets.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
 	// This is a vector of all the pallet types, not including system.
 	let pallet_types = pallets.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>();
+	// This is a vector of how each pallet dispatches its calls. Pallets with a currency are given
+	// the pallet named in their `#[currency(...)]` attribute.
+	let pallet_dispatches = pallet_names
+		.iter()
+		.zip(&currencies)
+		.map(|(name, currency)| match currency {
+			Some(currency) => quote! {
+				crate::support::DispatchWithCurrency::dispatch(
+					&mut self.#name,
+					&mut self.#currency,
+					caller,
+					call,
+				)?;
+			},
+			None => quote! {
+				crate::support::Dispatch::dispatch(&mut self.#name, caller, call)?;
+			},
+		})
+		.collect::<Vec<_>>();
 
 	// This quote block implements functions on the `Runtime` struct.
 	let runtime_impl = quote! {
 		impl #runtime_struct {
-			// Create a new instance of the main Runtime, by creating a new instance of each pallet.
-			fn new() -> Self {
-				Self {
+			// Create a new instance of the main Runtime, by creating a default instance of each pallet.
+			// Configuration problems are logged, since they point at a mistake in the runtime setup,
+			// and a runtime with configuration errors is refused.
+			fn new() -> Result<Self, Vec<crate::support::ConfigWarning>> {
+				crate::support::check_configuration(Self::validate_configuration())?;
+				Ok(Self {
 					// Since system is not included in the list of pallets, we manually add it here.
-					system: <system::Pallet::<Self>>::new(),
+					system: Default::default(),
 					#(
-						#pallet_names: <#pallet_types>::new()
+						#pallet_names: Default::default()
 					),*
+				})
+			}
+
+			// Check the `Config` of every pallet, including system, for inconsistent choices.
+			fn validate_configuration() -> Vec<crate::support::ConfigWarning> {
+				let mut warnings = vec![];
+				let checks = [
+					("system", <system::Pallet<Self> as crate::support::CheckConfig>::check_config()),
+					#(
+						(
+							stringify!(#pallet_names),
+							<#pallet_types as crate::support::CheckConfig>::check_config(),
+						)
+					),*
+				];
+				for (pallet, messages) in checks {
+					for (severity, message) in messages {
+						warnings.push(crate::support::ConfigWarning { pallet, severity, message });
+					}
 				}
+				warnings
 			}
 
 			// Execute a block of extrinsics. Increments the block number.
@@ -30,21 +72,95 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 				if block.header.block_number != self.system.block_number() {
 					return Err(&"block number does not match what is expected")
 				}
-				for (i, support::Extrinsic { caller, call }) in block.extrinsics.into_iter().enumerate() {
-					self.system.inc_nonce(&caller);
-					let _res = self.dispatch(caller, call).map_err(|e| {
+				// Events raised outside of a block, e.g. while setting up the chain, belong to no block.
+				#( crate::support::TakeEvents::take_events(&mut self.#pallet_names); )*
+				let block_number = self.system.block_number();
+				let block_author = self.system.block_author().cloned();
+				#( crate::support::OnBlockStart::on_block_start(&mut self.#pallet_names, block_number, block_author.as_ref()); )*
+				for (i, support::Extrinsic { caller, call, idempotency_key }) in block.extrinsics.into_iter().enumerate() {
+					// An extrinsic reusing a recent idempotency key, or whose caller can not pay the fee,
+					// is rejected before it does anything, so it can be sent again with the same key.
+					// Once the fee is paid, the key is used and the fee is kept, even if the call fails.
+					let result = match idempotency_key {
+						Some(key) => self.system.check_idempotency_key(&caller, key),
+						None => Ok(()),
+					};
+					let result = result
+						.and_then(|()| crate::support::ChargeFee::charge_fee(self, &caller))
+						.and_then(|()| match idempotency_key {
+							Some(key) => self.system.note_idempotency_key(&caller, key),
+							None => Ok(()),
+						})
+						.and_then(|()| {
+							self.system.inc_nonce(&caller);
+							// A failing call is undone in every pallet, so it leaves no partial state.
+							#( crate::support::Transactional::start_transaction(&mut self.#pallet_names); )*
+							let result = self.dispatch(caller, call);
+							if result.is_ok() {
+								#( crate::support::Transactional::commit_transaction(&mut self.#pallet_names); )*
+							} else {
+								#( crate::support::Transactional::rollback_transaction(&mut self.#pallet_names); )*
+							}
+							result
+						});
+					// Move the events raised by the extrinsic into the system pallet.
+					#(
+						for event in crate::support::TakeEvents::take_events(&mut self.#pallet_names) {
+							self.system.deposit_event(RuntimeEvent::#pallet_names(event));
+						}
+					)*
+					let _res = result.map_err(|e| {
 						eprintln!(
 							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
 							block.header.block_number, i, e
 						)
 					});
 				}
+				// During tests, every pallet's integrity checks run after each block.
+				if cfg!(test) {
+					if let Err((pallet, error)) = self.try_state_all() {
+						panic!("try_state failed after block: {}: {}", pallet, error);
+					}
+				}
+				Ok(())
+			}
+
+			// Run the integrity checks of every pallet, including system. On failure, returns the
+			// name of the pallet and the violated invariant.
+			fn try_state_all(&self) -> Result<(), (&'static str, &'static str)> {
+				let block_number = self.system.block_number();
+				crate::support::TryState::try_state(&self.system, block_number)
+					.map_err(|e| ("system", e))?;
+				#(
+					crate::support::TryState::try_state(&self.#pallet_names, block_number)
+						.map_err(|e| (stringify!(#pallet_names), e))?;
+				)*
+				Ok(())
+			}
+
+			// Execute well-formed empty blocks until the block number reaches `block_number`.
+			// This lets time advance when nobody sends extrinsics. Does nothing if the chain is
+			// already at or past `block_number`.
+			#[allow(dead_code)]
+			fn execute_empty_blocks_until(
+				&mut self,
+				block_number: <#runtime_struct as system::Config>::BlockNumber,
+			) -> crate::support::DispatchResult {
+				while self.system.block_number() < block_number {
+					let mut next = self.system.block_number();
+					next += <<#runtime_struct as system::Config>::BlockNumber as num::One>::one();
+					let block = types::Block {
+						header: support::Header { block_number: next },
+						extrinsics: vec![],
+					};
+					self.execute_block(block)?;
+				}
 				Ok(())
 			}
 		}
 	};
 
-	// This quote block implements the `RuntimeCall` enum and implements the `Dispatch` trait.
+	// This quote block implements the `RuntimeCall` and `RuntimeEvent` enums and implements the `Dispatch` trait.
 	let dispatch_impl = quote! {
 		// These are all the calls which are exposed to the world.
 		// Note that it is just an accumulation of the calls exposed by each pallet.
@@ -55,6 +171,13 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 			#( #pallet_names(#pallet_names::Call<#runtime_struct>) ),*
 		}
 
+		// These are all the events raised by the pallets, as stored by the system pallet.
+		#[allow(non_camel_case_types)]
+		#[derive(Debug, PartialEq)]
+		pub enum RuntimeEvent {
+			#( #pallet_names(<#pallet_names::Pallet<#runtime_struct> as crate::support::TakeEvents>::Event) ),*
+		}
+
 		impl crate::support::Dispatch for #runtime_struct {
 			type Caller = <Runtime as system::Config>::AccountId;
 			type Call = RuntimeCall;
@@ -73,7 +196,7 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 				match runtime_call {
 					#(
 						RuntimeCall::#pallet_names(call) => {
-							self.#pallet_names.dispatch(caller, call)?;
+							#pallet_dispatches
 						}
 					),*
 				}
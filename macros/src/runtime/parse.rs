@@ -8,6 +8,9 @@ pub struct RuntimeDef {
 	/// This is the list of pallets included in the `Runtime` struct. We omit `system` from this
 	/// list, but during parsing we check that system exists.
 	pub pallets: Vec<(syn::Ident, syn::Type)>,
+	/// For each pallet in `pallets`, the pallet given to it as its currency with
+	/// `#[currency(pallet_name)]`, if any.
+	pub currencies: Vec<Option<syn::Ident>>,
 }
 
 impl RuntimeDef {
@@ -26,14 +29,35 @@ impl RuntimeDef {
 
 		// Here is where we will store a list of all the pallets.
 		let mut pallets = vec![];
+		let mut currencies = vec![];
 		// We skip `system`, which we ensure is the first field in `check_system`.
 		for field in item_struct.fields.into_iter().skip(1) {
 			if let Some(ident) = field.ident {
+				currencies.push(parse_currency_attr(&field.attrs)?);
 				pallets.push((ident, field.ty))
 			}
 		}
 
-		Ok(Self { runtime_struct, pallets })
+		Ok(Self { runtime_struct, pallets, currencies })
+	}
+}
+
+/// This function finds the `#[currency(pallet_name)]` attribute of a pallet field, which names the
+/// pallet passed to the calls of this pallet as their currency.
+fn parse_currency_attr(attrs: &[syn::Attribute]) -> syn::Result<Option<syn::Ident>> {
+	match attrs.iter().find(|attr| attr.path().is_ident("currency")) {
+		Some(attr) => attr.parse_args::<syn::Ident>().map(Some),
+		None => Ok(None),
+	}
+}
+
+/// This function removes the `#[currency(...)]` attributes from the `Runtime` struct, since they
+/// only mean something to this macro.
+pub fn strip_currency_attrs(item: &mut syn::Item) {
+	if let syn::Item::Struct(item_struct) = item {
+		for field in item_struct.fields.iter_mut() {
+			field.attrs.retain(|attr| !attr.path().is_ident("currency"));
+		}
 	}
 }
 
@@ -0,0 +1,86 @@
+// Activity statistics of accounts: how many transfers each account sent and received, and how much
+// it moved. The balances pallet keeps them up to date through its transfer hooks, so only transfers
+// which went through, and were not rolled back, are counted.
+use crate::support::{OnBalanceChanged, OnTransfer};
+use num::traits::{SaturatingAdd, Zero};
+use std::collections::BTreeMap;
+
+// The transfers of one account.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccountActivity<Balance> {
+	pub sent: u32,                // The number of transfers sent.
+	pub received: u32,            // The number of transfers received.
+	pub volume_sent: Balance,     // The sum of the amounts sent.
+	pub volume_received: Balance, // The sum of the amounts received.
+}
+
+impl<Balance: Zero> Default for AccountActivity<Balance> {
+	fn default() -> Self {
+		Self { sent: 0, received: 0, volume_sent: Balance::zero(), volume_received: Balance::zero() }
+	}
+}
+
+// The activity of every account which sent or received a transfer. The counters saturate instead
+// of overflowing, since they are only statistics.
+#[derive(Debug)]
+pub struct ActivityStatistics<AccountId, Balance> {
+	activity: BTreeMap<AccountId, AccountActivity<Balance>>,
+}
+
+impl<AccountId, Balance> Default for ActivityStatistics<AccountId, Balance> {
+	fn default() -> Self {
+		Self { activity: BTreeMap::new() }
+	}
+}
+
+impl<AccountId: Ord, Balance: Zero + Copy> ActivityStatistics<AccountId, Balance> {
+	// Get the activity of `who`, which is all zero for an account without any transfer.
+	pub fn activity(&self, who: &AccountId) -> AccountActivity<Balance> {
+		self.activity.get(who).copied().unwrap_or_default()
+	}
+}
+
+impl<AccountId: Ord + Clone, Balance: SaturatingAdd + Zero + Copy> OnTransfer<AccountId, Balance>
+	for ActivityStatistics<AccountId, Balance>
+{
+	fn on_transfer(&mut self, from: &AccountId, to: &AccountId, amount: &Balance) {
+		let sender = self.activity.entry(from.clone()).or_default();
+		sender.sent = sender.sent.saturating_add(1);
+		sender.volume_sent = sender.volume_sent.saturating_add(amount);
+		let receiver = self.activity.entry(to.clone()).or_default();
+		receiver.received = receiver.received.saturating_add(1);
+		receiver.volume_received = receiver.volume_received.saturating_add(amount);
+	}
+}
+
+// Balance changes outside of transfers, e.g. fees or mints, are not activity.
+impl<AccountId, Balance> OnBalanceChanged<AccountId, Balance> for ActivityStatistics<AccountId, Balance> {
+	fn on_balance_changed(&mut self, _who: &AccountId, _new_free: &Balance) {}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{AccountActivity, ActivityStatistics};
+	use crate::support::{OnBalanceChanged, OnTransfer};
+
+	#[test]
+	fn activity_statistics() {
+		let mut statistics = ActivityStatistics::<&'static str, u8>::default();
+		statistics.on_transfer(&"alice", &"bob", &30);
+		statistics.on_transfer(&"bob", &"alice", &250);
+		statistics.on_transfer(&"alice", &"bob", &30);
+		statistics.on_balance_changed(&"alice", &0); // Not a transfer.
+
+		assert_eq!(
+			statistics.activity(&"alice"),
+			AccountActivity { sent: 2, received: 1, volume_sent: 60, volume_received: 250 }
+		);
+		assert_eq!(
+			statistics.activity(&"bob"),
+			AccountActivity { sent: 1, received: 2, volume_sent: 250, volume_received: 60 }
+		);
+		statistics.on_transfer(&"bob", &"charlie", &10);
+		assert_eq!(statistics.activity(&"bob").volume_sent, u8::MAX); // Saturated.
+		assert_eq!(statistics.activity(&"dave"), AccountActivity::default());
+	}
+}
@@ -1,11 +1,332 @@
 // The balance pallet manages the balances of users and allow them to transfer tokens to one another.
 
-use num::traits::{CheckedAdd, CheckedSub, Zero}; // can import traits which define types which expose functions.
-use std::collections::BTreeMap; // used to map user addresses to balances.
+use crate::support::{
+    CheckConfig, ConfigSeverity, Currency, Get, OnBalanceChanged, OnBlockStart, OnTransfer,
+    TakeEvents, Transactional, TryState,
+}; // hooks for pallets reacting to balance changes.
+use core::fmt::Debug;
+use num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Zero}; // can import traits which define types which expose functions.
+use std::collections::{BTreeMap, BTreeSet}; // used to map user addresses to balances.
 
 // Here you are making these types configurable in the future.
 pub trait Config: crate::system::Config {
-    type Balance: Zero + CheckedSub + CheckedAdd + Copy;
+    // Block numbers convert into balances, so vesting can unlock an amount per block, and so do
+    // share weights, so a total can be split by shares.
+    type Balance: Zero
+        + CheckedSub
+        + CheckedAdd
+        + CheckedMul
+        + CheckedDiv
+        + From<Self::BlockNumber>
+        + From<u32>
+        + Copy
+        + Ord
+        + Debug;
+    // The minimum balance an account needs to exist. Accounts falling below it are reaped, and their
+    // remaining dust goes to the dust collector. Zero disables reaping.
+    const EXISTENTIAL_DEPOSIT: Self::Balance;
+    // The account which collects the dust of reaped accounts. Use `()` to burn the dust instead.
+    type DustCollector: Get<Option<Self::AccountId>>;
+    // Listeners notified after balances change. Use `()` for none, or a tuple for several. The
+    // pallet owns the listeners, starting from their default.
+    type TransferHooks: OnTransfer<Self::AccountId, Self::Balance>
+        + OnBalanceChanged<Self::AccountId, Self::Balance>
+        + Default
+        + Debug;
+    // The maximum number of accounts in the list of a transfer policy.
+    const MAX_TRANSFER_POLICY_LEN: usize;
+    // Identifies the assets which exist next to the native token.
+    type AssetId: Ord + Copy + Debug;
+    // The flat fee every extrinsic pays before its call is dispatched.
+    const TRANSACTION_FEE: Self::Balance;
+    // The maximum length in bytes of the memo attached to a transfer.
+    const MAX_MEMO_LEN: usize;
+}
+
+// How a transfer policy uses its list of counterparties.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferMode {
+    AllowAll,        // The list is ignored, every counterparty is allowed.
+    DenyListed,      // Every counterparty except the listed ones is allowed.
+    AllowListedOnly, // Only the listed counterparties are allowed.
+}
+
+// The restriction an account places on who it sends funds to and receives funds from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferPolicy<AccountId> {
+    pub mode: TransferMode,
+    pub list: Vec<AccountId>, // At most `Config::MAX_TRANSFER_POLICY_LEN` accounts.
+}
+
+impl<AccountId: Ord> TransferPolicy<AccountId> {
+    // Check if this policy allows transfers with `counterparty`.
+    pub fn allows(&self, counterparty: &AccountId) -> bool {
+        match self.mode {
+            TransferMode::AllowAll => true,
+            TransferMode::DenyListed => !self.list.contains(counterparty),
+            TransferMode::AllowListedOnly => self.list.contains(counterparty),
+        }
+    }
+}
+
+// Identifies a lock on an account, so several pallets can each hold their own lock.
+pub type LockIdentifier = [u8; 8];
+
+// The new balances of the sender and the receiver after a transfer, or `None` when the transfer
+// left them unchanged.
+type AppliedTransfer<Balance> = Option<(Balance, Balance)>;
+
+// How the tip of a transfer was paid.
+enum PaidTip<AccountId, Balance> {
+    Nothing,                                       // The tip was zero.
+    ToAuthor(AccountId, AppliedTransfer<Balance>), // Sent to the block author.
+    Burned(Balance),                               // Burned, leaving the caller with this balance.
+}
+
+// A value overwritten while a transaction is open, with what it was before, so it can be restored
+// by `rollback_transaction`. A `None` value means there was no entry.
+#[derive(Debug)]
+enum JournalEntry<AccountId, Balance> {
+    Balance(AccountId, Option<Balance>),
+    Reserved(AccountId, Option<Balance>),
+    Allowance((AccountId, AccountId), Option<Balance>),
+    Spent(AccountId, Option<Balance>),
+    Totals {
+        total_issuance: Balance,
+        collected_fees: Balance,
+        collected_dust: Balance,
+    },
+}
+
+// A notification of the transfer hooks, held back until the outermost transaction commits.
+#[derive(Debug)]
+enum HookCall<AccountId, Balance> {
+    Transfer(AccountId, AccountId, Balance),
+    BalanceChanged(AccountId, Balance),
+}
+
+// Set the entry of `key` in `map` back to `value`, removing it when there was none.
+fn restore_entry<K: Ord, V>(map: &mut BTreeMap<K, V>, key: K, value: Option<V>) {
+    match value {
+        Some(value) => map.insert(key, value),
+        None => map.remove(&key),
+    };
+}
+
+// The lock under which the unvested funds of an account are held.
+pub const VESTING_ID: LockIdentifier = *b"vesting ";
+
+// Funds which unlock little by little: `per_block` of the `locked` amount is released every block
+// from `starting_block` on, until all of it is released.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VestingSchedule<Balance, BlockNumber> {
+    pub locked: Balance,
+    pub per_block: Balance,
+    pub starting_block: BlockNumber,
+}
+
+// An asset which exists next to the native token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetDetails<AccountId, Balance> {
+    pub owner: AccountId, // The creator of the asset, the only account allowed to mint it.
+    pub total_issuance: Balance, // The sum of all balances of the asset.
+}
+
+// The events of the balances pallet, in the order they happened.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountId, Balance> {
+    // `amount` was sent from `from` to `to`.
+    Transfer {
+        from: AccountId,
+        to: AccountId,
+        amount: Balance,
+    },
+    // `amount` was sent from `from` to `to`, with a `memo` chosen by the sender.
+    TransferWithMemo {
+        from: AccountId,
+        to: AccountId,
+        amount: Balance,
+        memo: Vec<u8>,
+    },
+    // The free balance of `who` was set to `amount`, outside of any transfer.
+    BalanceSet {
+        who: AccountId,
+        amount: Balance,
+    },
+    // `amount` new tokens were created in the account `who`.
+    Minted {
+        who: AccountId,
+        amount: Balance,
+    },
+    // `amount` tokens of the account `who` were destroyed.
+    Burned {
+        who: AccountId,
+        amount: Balance,
+    },
+    // `amount` tokens of the account `who` were destroyed as a punishment.
+    Slashed {
+        who: AccountId,
+        amount: Balance,
+    },
+    // `amount` was withdrawn from the account `who` by another pallet, for `reason`.
+    Withdrawn {
+        who: AccountId,
+        amount: Balance,
+        reason: WithdrawReason,
+    },
+    // `amount` was deposited to the account `who` by another pallet.
+    Deposited {
+        who: AccountId,
+        amount: Balance,
+    },
+    // The account `who` was reaped, and its dust `amount` was burned.
+    DustLost {
+        who: AccountId,
+        amount: Balance,
+    },
+    // The account `who` was reaped, and its dust `amount` was moved to `collector`.
+    DustCollected {
+        who: AccountId,
+        collector: AccountId,
+        amount: Balance,
+    },
+}
+
+// Why funds are withdrawn from an account, which decides the checks of the withdrawal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WithdrawReason {
+    Fee,      // A fee. Locked funds can not pay it.
+    Transfer, // Funds sent elsewhere. Frozen accounts and locked funds can not send them.
+    Reserve, // Funds moved to the reserved balance, see `reserve`. Locked funds can not be reserved.
+    Slash,   // A punishment, which takes locked funds too.
+}
+
+// The errors of the balances pallet. At the runtime level, each error becomes its message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BalancesError {
+    // The account does not have enough free funds.
+    InsufficientFunds,
+    // The account does not have enough free funds to reserve.
+    InsufficientFreeFunds,
+    // The account does not have enough reserved funds.
+    InsufficientReservedFunds,
+    // The account can not pay the transaction fee.
+    CannotPayFee,
+    // A balance or an amount overflowed.
+    Overflow,
+    // Minting would overflow the balance of the receiver.
+    BalanceOverflow,
+    // The total issuance would overflow.
+    IssuanceOverflow,
+    // The total issuance would underflow.
+    IssuanceUnderflow,
+    // The account would be created with less than the existential deposit.
+    BelowExistentialDeposit,
+    // The funds are held by a lock.
+    FundsLocked,
+    // The call is reserved to the root account.
+    NotRoot,
+    // The sender is frozen.
+    FrozenAccount,
+    // The transfer policy of the sender does not allow the transfer.
+    SenderRestricted,
+    // The transfer policy of the receiver does not allow the transfer.
+    RecipientRestricted,
+    // The transfer policy list is longer than `MAX_TRANSFER_POLICY_LEN`.
+    PolicyListTooLong,
+    // Accounts can only burn their own funds.
+    NotOwnFunds,
+    // An account is listed twice in the genesis config.
+    DuplicateGenesisAccount,
+    // The genesis config is built into a pallet which already holds funds.
+    GenesisNotEmpty,
+    // The owner gave no allowance to the spender.
+    NoAllowance,
+    // The allowance is lower than the amount.
+    AllowanceTooLow,
+    // Funds spent through an allowance can not go back to their owner.
+    SelfTransfer,
+    // An asset with this id already exists.
+    AssetAlreadyExists,
+    // No asset with this id exists.
+    UnknownAsset,
+    // Only the creator of an asset can mint it.
+    NotAssetCreator,
+    // The memo is longer than `MAX_MEMO_LEN`.
+    MemoTooLong,
+    // A split needs at least one recipient.
+    NoRecipients,
+    // Share weights can not be zero.
+    ZeroShare,
+    // A vesting schedule must unlock something every block.
+    VestingNeverUnlocks,
+    // The receiver already has a vesting schedule.
+    VestingScheduleExists,
+    // Accounts can not vest funds to themselves.
+    VestToSelf,
+    // The account has no vesting schedule.
+    NoVestingSchedule,
+    // Transfers of zero are not allowed.
+    ZeroTransfer,
+    // The amount is below the minimum transfer.
+    BelowMinimum,
+    // The transfer would exceed the spending limit of the sender for this block.
+    SpendingLimitExceeded,
+}
+
+impl BalancesError {
+    // The message describing the error.
+    pub fn message(&self) -> &'static str {
+        match self {
+            BalancesError::InsufficientFunds => "Not enough funds.",
+            BalancesError::InsufficientFreeFunds => "Not enough free funds",
+            BalancesError::InsufficientReservedFunds => "Not enough reserved funds",
+            BalancesError::CannotPayFee => "Not enough funds to pay the fee",
+            BalancesError::Overflow => "Overflow",
+            BalancesError::BalanceOverflow => "Balance overflow",
+            BalancesError::IssuanceOverflow => "Total issuance overflow",
+            BalancesError::IssuanceUnderflow => "Total issuance underflow",
+            BalancesError::BelowExistentialDeposit => "amount below existential deposit",
+            BalancesError::FundsLocked => "funds are locked",
+            BalancesError::NotRoot => "caller is not root",
+            BalancesError::FrozenAccount => "account is frozen",
+            BalancesError::SenderRestricted => "sender restricted",
+            BalancesError::RecipientRestricted => "recipient restricted",
+            BalancesError::PolicyListTooLong => "transfer policy list too long",
+            BalancesError::NotOwnFunds => "can only burn own funds",
+            BalancesError::DuplicateGenesisAccount => "duplicate account in genesis",
+            BalancesError::GenesisNotEmpty => "genesis needs an empty pallet",
+            BalancesError::NoAllowance => "no allowance",
+            BalancesError::AllowanceTooLow => "allowance too low",
+            BalancesError::SelfTransfer => "can not transfer from an account to itself",
+            BalancesError::AssetAlreadyExists => "asset already exists",
+            BalancesError::UnknownAsset => "unknown asset",
+            BalancesError::NotAssetCreator => "only the asset creator can mint",
+            BalancesError::MemoTooLong => "memo too long",
+            BalancesError::NoRecipients => "no recipients",
+            BalancesError::ZeroShare => "zero share",
+            BalancesError::VestingNeverUnlocks => "vesting schedule never unlocks",
+            BalancesError::VestingScheduleExists => "account already has a vesting schedule",
+            BalancesError::VestToSelf => "can not vest funds to yourself",
+            BalancesError::NoVestingSchedule => "no vesting schedule",
+            BalancesError::ZeroTransfer => "transfer of zero",
+            BalancesError::BelowMinimum => "transfer below minimum",
+            BalancesError::SpendingLimitExceeded => "spending limit exceeded for this block",
+        }
+    }
+}
+
+impl core::fmt::Display for BalancesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+// Lets balances calls return their errors to the runtime, which dispatches with `DispatchResult`.
+impl From<BalancesError> for &'static str {
+    fn from(error: BalancesError) -> Self {
+        error.message()
+    }
 }
 
 // The "pub struct" provides the entry point into the Pallet.
@@ -13,6 +334,28 @@ pub trait Config: crate::system::Config {
 pub struct Pallet<T: Config> {
     // "T: Config" is used to make the pallet configurable and scalable.
     balances: BTreeMap<T::AccountId, T::Balance>, // This is used to match account ID with their balances.
+    transfer_policies: BTreeMap<T::AccountId, TransferPolicy<T::AccountId>>, // Accounts without a policy allow all transfers.
+    reserved: BTreeMap<T::AccountId, T::Balance>, // Funds set aside by other pallets, which can not be spent.
+    locks: BTreeMap<T::AccountId, BTreeMap<LockIdentifier, T::Balance>>, // Named locks on free funds, the largest one applies.
+    allowances: BTreeMap<(T::AccountId, T::AccountId), T::Balance>, // How much each (owner, spender) pair may spend.
+    root_account: Option<T::AccountId>, // The privileged account allowed to make root-only calls, if any.
+    total_issuance: T::Balance, // The sum of all free and reserved balances and fees, i.e. how many tokens exist.
+    events: Vec<Event<T::AccountId, T::Balance>>, // Events not yet collected by the runtime.
+    assets: BTreeMap<T::AssetId, AssetDetails<T::AccountId, T::Balance>>, // The assets created so far.
+    asset_balances: BTreeMap<(T::AssetId, T::AccountId), T::Balance>, // The balance of each account in each asset.
+    collected_fees: T::Balance, // The transaction fees paid so far, still part of the total issuance.
+    collected_dust: T::Balance, // The dust of reaped accounts moved to the dust collector so far.
+    frozen: BTreeSet<T::AccountId>, // Accounts which can not send funds, but can still receive them.
+    vesting: BTreeMap<T::AccountId, VestingSchedule<T::Balance, T::BlockNumber>>, // Funds still being unlocked.
+    block_number: T::BlockNumber, // The current block number, as told by the runtime when a block starts.
+    minimum_transfer: T::Balance, // The smallest amount a transfer can send, on top of it not being zero.
+    block_author: Option<T::AccountId>, // The author of the current block, who receives the tips.
+    spending_limits: BTreeMap<T::AccountId, T::Balance>, // How much each account allows itself to send per block.
+    spent_this_block: BTreeMap<T::AccountId, T::Balance>, // How much each limited account sent in the current block.
+    journal: Vec<JournalEntry<T::AccountId, T::Balance>>, // The values overwritten since the outermost open transaction started.
+    transactions: Vec<(usize, usize, usize)>, // The journal, event and pending hook lengths when each open transaction started, innermost last.
+    hooks: T::TransferHooks,                  // The listeners notified after balances change.
+    pending_hooks: Vec<HookCall<T::AccountId, T::Balance>>, // Notifications held back until the outermost transaction commits.
 }
 
 // Here you are implementing the Pallet and specifying you want it to be configurable.
@@ -21,11 +364,421 @@ impl<T: Config> Pallet<T> {
     pub fn new() -> Self {
         Self {
             balances: BTreeMap::new(),
+            transfer_policies: BTreeMap::new(),
+            reserved: BTreeMap::new(),
+            locks: BTreeMap::new(),
+            allowances: BTreeMap::new(),
+            root_account: None,
+            total_issuance: T::Balance::zero(),
+            events: Vec::new(),
+            assets: BTreeMap::new(),
+            asset_balances: BTreeMap::new(),
+            collected_fees: T::Balance::zero(),
+            collected_dust: T::Balance::zero(),
+            frozen: BTreeSet::new(),
+            vesting: BTreeMap::new(),
+            block_number: T::BlockNumber::zero(),
+            minimum_transfer: T::Balance::zero(),
+            block_author: None,
+            spending_limits: BTreeMap::new(),
+            spent_this_block: BTreeMap::new(),
+            journal: Vec::new(),
+            transactions: Vec::new(),
+            hooks: Default::default(),
+            pending_hooks: Vec::new(),
         }
     }
 
-    // Set the balance of an account `who` to some `amount`.
+    // Create a pallet where each account starts with its `initial` balance, like a genesis
+    // config without a minimum transfer. Fails if an account is listed twice, or if a balance is
+    // below the existential deposit.
+    pub fn new_with_balances(
+        initial: impl IntoIterator<Item = (T::AccountId, T::Balance)>,
+    ) -> Result<Self, &'static str> {
+        let mut pallet = Self::new();
+        let genesis = GenesisConfig::<T> {
+            balances: initial.into_iter().collect(),
+            minimum_transfer: T::Balance::zero(),
+        };
+        genesis.build(&mut pallet)?;
+        Ok(pallet)
+    }
+
+    // Set the free balance of an account `who` to some `amount`.
+    // The total issuance is adjusted by the difference with the old balance.
+    // Panics if the total issuance overflows. This is meant for tests, extrinsics use the root-only
+    // `force_set_balance` call instead.
     pub fn set_balance(&mut self, who: &T::AccountId, amount: T::Balance) {
+        self.note_totals();
+        self.total_issuance = self
+            .total_issuance
+            .checked_sub(&self.balance(who))
+            .and_then(|issuance| issuance.checked_add(&amount))
+            .expect("total issuance overflow");
+        self.write_balance(who.clone(), amount);
+        self.notify_balance_changed(who, &amount);
+        self.events.push(Event::BalanceSet {
+            who: who.clone(),
+            amount,
+        });
+    }
+
+    // Store the balance of `who`. A zero balance removes the entry, since `balance` already
+    // returns zero for unknown accounts.
+    fn write_balance(&mut self, who: T::AccountId, amount: T::Balance) {
+        self.note_balance(&who);
+        if amount.is_zero() {
+            self.balances.remove(&who);
+        } else {
+            self.balances.insert(who, amount);
+        }
+    }
+
+    // Store the balance of `who` after funds left the account. If it falls below the existential
+    // deposit the account is reaped: its entry is removed and the dust is collected. Accounts with
+    // reserved funds or locks are kept alive. Returns the new balance, which is zero for a reaped
+    // account.
+    fn write_balance_or_reap(&mut self, who: T::AccountId, amount: T::Balance) -> T::Balance {
+        if amount >= T::EXISTENTIAL_DEPOSIT || self.is_kept_alive(&who) {
+            self.write_balance(who, amount);
+            return amount;
+        }
+        self.write_balance(who.clone(), T::Balance::zero());
+        self.collect_dust(&who, amount);
+        T::Balance::zero()
+    }
+
+    // Reap `who` when nothing keeps it alive any more, i.e. once its last lock or reserve is gone,
+    // while its free balance is below the existential deposit.
+    fn reap_if_dust(&mut self, who: &T::AccountId) {
+        let free = self.balance(who);
+        if free.is_zero() || free >= T::EXISTENTIAL_DEPOSIT || self.is_kept_alive(who) {
+            return;
+        }
+        self.write_balance(who.clone(), T::Balance::zero());
+        self.collect_dust(who, free);
+        self.notify_balance_changed(who, &T::Balance::zero());
+    }
+
+    // Move the `dust` left by reaping `who` to the dust collector. The dust is burned instead when
+    // there is no collector, when the collector itself was reaped, when the collector would still
+    // be below the existential deposit, or when its balance would overflow.
+    fn collect_dust(&mut self, who: &T::AccountId, dust: T::Balance) {
+        if dust.is_zero() {
+            return;
+        }
+        self.note_totals();
+        let collected = T::DustCollector::get()
+            .filter(|collector| collector != who)
+            .and_then(|collector| {
+                let new_balance = self.balance(&collector).checked_add(&dust)?;
+                let collected_dust = self.collected_dust.checked_add(&dust)?;
+                (new_balance >= T::EXISTENTIAL_DEPOSIT).then_some((
+                    collector,
+                    new_balance,
+                    collected_dust,
+                ))
+            });
+        match collected {
+            Some((collector, new_balance, collected_dust)) => {
+                self.write_balance(collector.clone(), new_balance);
+                self.collected_dust = collected_dust;
+                self.events.push(Event::DustCollected {
+                    who: who.clone(),
+                    collector,
+                    amount: dust,
+                });
+            }
+            // The dust is part of the issuance, which is the sum of all balances, so this can not fail.
+            None => {
+                if let Some(issuance) = self.total_issuance.checked_sub(&dust) {
+                    self.total_issuance = issuance;
+                }
+                self.events.push(Event::DustLost {
+                    who: who.clone(),
+                    amount: dust,
+                });
+            }
+        }
+    }
+
+    // Get the dust of reaped accounts moved to the dust collector so far.
+    pub fn collected_dust(&self) -> T::Balance {
+        self.collected_dust
+    }
+
+    // Check that the account `to` can receive `amount`. New accounts must receive at least the
+    // existential deposit, so no transfer creates dust.
+    fn check_existential_deposit(
+        &self,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        if !self.account_exists(to) && amount < T::EXISTENTIAL_DEPOSIT {
+            return Err(BalancesError::BelowExistentialDeposit);
+        }
+        Ok(())
+    }
+
+    // Check if the account `who` exists, i.e. holds at least the existential deposit or has
+    // reserved funds.
+    pub fn account_exists(&self, who: &T::AccountId) -> bool {
+        self.balances.contains_key(who) || self.reserved.contains_key(who)
+    }
+
+    // Check if `who` has reserved funds or locks, which keep the account from being reaped.
+    fn is_kept_alive(&self, who: &T::AccountId) -> bool {
+        self.reserved.contains_key(who) || self.locks.contains_key(who)
+    }
+
+    // Get the amount of free funds of `who` which can not be spent, i.e. its largest lock.
+    pub fn locked_balance(&self, who: &T::AccountId) -> T::Balance {
+        let mut locked = T::Balance::zero();
+        for amount in self
+            .locks
+            .get(who)
+            .into_iter()
+            .flat_map(|locks| locks.values())
+        {
+            if *amount > locked {
+                locked = *amount;
+            }
+        }
+        locked
+    }
+
+    // Get the free funds of `who` which can be spent, i.e. the free balance minus its largest lock.
+    pub fn spendable_balance(&self, who: &T::AccountId) -> T::Balance {
+        self.balance(who)
+            .checked_sub(&self.locked_balance(who))
+            .unwrap_or(T::Balance::zero())
+    }
+
+    // Check that a free balance of `new_free` left after a withdrawal still covers the locks of
+    // `who`.
+    fn check_locks(&self, who: &T::AccountId, new_free: T::Balance) -> Result<(), BalancesError> {
+        if new_free < self.locked_balance(who) {
+            return Err(BalancesError::FundsLocked);
+        }
+        Ok(())
+    }
+
+    // Lock `amount` of the free funds of `who` under the lock `id`. Locks do not stack: setting an
+    // existing `id` overwrites it, and only the largest lock restricts spending. Incoming funds are
+    // never affected.
+    pub fn set_lock(&mut self, id: LockIdentifier, who: &T::AccountId, amount: T::Balance) {
+        if amount.is_zero() {
+            self.remove_lock(id, who);
+            return;
+        }
+        self.locks
+            .entry(who.clone())
+            .or_default()
+            .insert(id, amount);
+    }
+
+    // Remove the lock `id` from `who`, if it exists. Removing the last lock reaps an account left
+    // with dust.
+    pub fn remove_lock(&mut self, id: LockIdentifier, who: &T::AccountId) {
+        if let Some(locks) = self.locks.get_mut(who) {
+            locks.remove(&id);
+            if locks.is_empty() {
+                self.locks.remove(who);
+                self.reap_if_dust(who);
+            }
+        }
+    }
+
+    // Get the vesting schedule of `who`, if it has one.
+    pub fn vesting(
+        &self,
+        who: &T::AccountId,
+    ) -> Option<&VestingSchedule<T::Balance, T::BlockNumber>> {
+        self.vesting.get(who)
+    }
+
+    // Get how much of the vesting schedule of `who` is still locked at the current block.
+    pub fn vesting_balance(&self, who: &T::AccountId) -> T::Balance {
+        match self.vesting.get(who) {
+            Some(schedule) => Self::still_vesting(schedule, self.block_number),
+            None => T::Balance::zero(),
+        }
+    }
+
+    // Get how much of `schedule` is still locked at `block_number`.
+    fn still_vesting(
+        schedule: &VestingSchedule<T::Balance, T::BlockNumber>,
+        block_number: T::BlockNumber,
+    ) -> T::Balance {
+        let elapsed = T::Balance::from(block_number)
+            .checked_sub(&T::Balance::from(schedule.starting_block))
+            .unwrap_or(T::Balance::zero());
+        // An overflowing product is more than any schedule locks.
+        let vested = elapsed
+            .checked_mul(&schedule.per_block)
+            .unwrap_or(schedule.locked);
+        schedule
+            .locked
+            .checked_sub(&vested)
+            .unwrap_or(T::Balance::zero())
+    }
+
+    // Lock what is still vesting for `who`, and drop the schedule once everything is unlocked.
+    fn update_vesting_lock(&mut self, who: &T::AccountId) {
+        let still_vesting = self.vesting_balance(who);
+        self.set_lock(VESTING_ID, who, still_vesting);
+        if still_vesting.is_zero() {
+            self.vesting.remove(who);
+        }
+    }
+
+    // Get the reserved balance of an account `who`.
+    pub fn reserved_balance(&self, who: &T::AccountId) -> T::Balance {
+        *self.reserved.get(who).unwrap_or(&T::Balance::zero())
+    }
+
+    // Store the reserved balance of `who`, removing the entry when it is zero.
+    fn write_reserved(&mut self, who: T::AccountId, amount: T::Balance) {
+        if !self.transactions.is_empty() {
+            let previous = self.reserved.get(&who).copied();
+            self.journal
+                .push(JournalEntry::Reserved(who.clone(), previous));
+        }
+        if amount.is_zero() {
+            self.reserved.remove(&who);
+        } else {
+            self.reserved.insert(who, amount);
+        }
+    }
+
+    // Destroy up to `amount` of the free balance of `who` as a punishment, decreasing the total
+    // issuance. Locks do not protect funds from being slashed. Returns how much was slashed, which
+    // is less than `amount` when the account does not have enough.
+    pub fn slash(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+        let free = self.balance(who);
+        let slashed = if amount < free { amount } else { free };
+        if slashed.is_zero() {
+            return slashed;
+        }
+        // `slashed` is part of the free balance, so neither subtraction can fail.
+        let new_free = free.checked_sub(&slashed).unwrap_or(free);
+        self.note_totals();
+        if let Some(issuance) = self.total_issuance.checked_sub(&slashed) {
+            self.total_issuance = issuance;
+        }
+        let new_free = self.write_balance_or_reap(who.clone(), new_free);
+        self.notify_balance_changed(who, &new_free);
+        self.events.push(Event::Slashed {
+            who: who.clone(),
+            amount: slashed,
+        });
+        slashed
+    }
+
+    // Take the transaction `fee` from the free balance of `who` into the collected fees.
+    // Fails without changing anything if `who` can not pay it.
+    pub fn withdraw_fee(
+        &mut self,
+        who: &T::AccountId,
+        fee: T::Balance,
+    ) -> Result<(), BalancesError> {
+        let new_collected_fees = self
+            .collected_fees
+            .checked_add(&fee)
+            .ok_or(BalancesError::Overflow)?;
+        self.withdraw(who, fee, WithdrawReason::Fee)
+            .map_err(|error| match error {
+                BalancesError::InsufficientFunds => BalancesError::CannotPayFee,
+                error => error,
+            })?;
+        // The collected fees are still part of the issuance, which `withdraw` just lowered by `fee`.
+        self.total_issuance = self
+            .total_issuance
+            .checked_add(&fee)
+            .ok_or(BalancesError::IssuanceOverflow)?;
+        self.collected_fees = new_collected_fees;
+        Ok(())
+    }
+
+    // Get the sum of the transaction fees paid so far.
+    pub fn collected_fees(&self) -> T::Balance {
+        self.collected_fees
+    }
+
+    // Move `amount` from the free balance of `who` to its reserved balance, so it can not be spent.
+    // Used by other pallets to set funds aside without destroying them.
+    pub fn reserve(&mut self, who: &T::AccountId, amount: T::Balance) -> Result<(), BalancesError> {
+        let new_free = self
+            .balance(who)
+            .checked_sub(&amount)
+            .ok_or(BalancesError::InsufficientFreeFunds)?;
+        self.check_locks(who, new_free)?;
+        let new_reserved = self
+            .reserved_balance(who)
+            .checked_add(&amount)
+            .ok_or(BalancesError::Overflow)?;
+
+        // Reserve first, so the account is kept alive even if its free balance drops to dust.
+        self.write_reserved(who.clone(), new_reserved);
+        self.write_balance(who.clone(), new_free);
+        self.notify_balance_changed(who, &new_free);
+        Ok(())
+    }
+
+    // Move up to `amount` from the reserved balance of `who` back to its free balance.
+    // Returns how much was actually unreserved, which is at most the reserved balance. Unreserving
+    // everything reaps an account left with dust.
+    pub fn unreserve(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+        let reserved = self.reserved_balance(who);
+        let actual = if amount < reserved { amount } else { reserved };
+        // Free and reserved funds are both part of the issuance, so their sum can not overflow.
+        let (Some(new_free), Some(new_reserved)) = (
+            self.balance(who).checked_add(&actual),
+            reserved.checked_sub(&actual),
+        ) else {
+            return T::Balance::zero();
+        };
+
+        self.write_balance(who.clone(), new_free);
+        self.write_reserved(who.clone(), new_reserved);
+        self.notify_balance_changed(who, &new_free);
+        self.reap_if_dust(who);
+        actual
+    }
+
+    // Move `amount` from the reserved balance of `from` straight to the free balance of `to`, e.g.
+    // to pay out an escrow. The funds were set aside already, so the freeze, locks, spending limit
+    // and minimum transfer of `from` do not apply, but the transfer policies of both sides do.
+    // `from` is reaped if its free balance is dust once nothing is reserved any more.
+    pub fn repatriate_reserved(
+        &mut self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        let new_reserved = self
+            .reserved_balance(from)
+            .checked_sub(&amount)
+            .ok_or(BalancesError::InsufficientReservedFunds)?;
+        self.check_transfer_policy(from, to)?;
+        let new_to_balance = self.check_deposit(to, amount)?;
+
+        self.write_reserved(from.clone(), new_reserved);
+        self.write_deposit(to, new_to_balance);
+        self.notify_transfer(from, to, &amount);
+        self.notify_balance_changed(to, &new_to_balance);
+        self.reap_if_dust(from);
+        self.events.push(Event::Transfer {
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+        });
+        Ok(())
+    }
+
+    // Store a balance without any checks, so tests can break the pallet invariants.
+    #[cfg(test)]
+    pub fn insert_raw_balance(&mut self, who: &T::AccountId, amount: T::Balance) {
         self.balances.insert(who.clone(), amount);
     }
 
@@ -34,76 +787,3432 @@ impl<T: Config> Pallet<T> {
     pub fn balance(&self, who: &T::AccountId) -> T::Balance {
         *self.balances.get(who).unwrap_or(&T::Balance::zero())
     }
-}
-#[macros::call]
-impl<T: Config> Pallet<T> {
-    // Transfer `amount` from one account to another.
-    // This function verifies that `from` has at least `amount` balance to transfer,
-    // and that no mathematical overflows occur. We made everything generic and customizable.
-    pub fn transfer(
+
+    // Set the privileged account allowed to make root-only calls. `None` disables them.
+    pub fn set_root_account(&mut self, root: Option<T::AccountId>) {
+        self.root_account = root;
+    }
+
+    // Iterate over the accounts with a free balance, sorted by account.
+    pub fn accounts(&self) -> impl Iterator<Item = (&T::AccountId, &T::Balance)> {
+        self.balances.iter()
+    }
+
+    // Get the number of accounts with a free balance.
+    pub fn account_count(&self) -> usize {
+        self.balances.len()
+    }
+
+    // Get the sum of the free balances of `accounts`. Fails if the sum overflows.
+    pub fn total_of(&self, accounts: &[T::AccountId]) -> Result<T::Balance, BalancesError> {
+        accounts
+            .iter()
+            .try_fold(T::Balance::zero(), |sum, who| {
+                sum.checked_add(&self.balance(who))
+            })
+            .ok_or(BalancesError::Overflow)
+    }
+
+    // Get the `n` accounts with the largest free balances, largest first. Accounts with equal
+    // balances are sorted by account.
+    pub fn richest(&self, n: usize) -> Vec<(T::AccountId, T::Balance)> {
+        let mut accounts = self.balances.iter().collect::<Vec<_>>();
+        // The map is sorted by account, and the stable sort keeps that order among equal balances.
+        accounts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        accounts
+            .into_iter()
+            .take(n)
+            .map(|(who, balance)| (who.clone(), *balance))
+            .collect()
+    }
+
+    // Get the number of accounts whose free balance is strictly above `threshold`.
+    pub fn holders_above(&self, threshold: T::Balance) -> usize {
+        self.balances
+            .values()
+            .filter(|balance| **balance > threshold)
+            .count()
+    }
+
+    // Get the privileged account allowed to make root-only calls, if any.
+    pub fn root_account(&self) -> Option<&T::AccountId> {
+        self.root_account.as_ref()
+    }
+
+    // Get the smallest amount a transfer can send.
+    pub fn minimum_transfer(&self) -> T::Balance {
+        self.minimum_transfer
+    }
+
+    // Check if the account `who` is frozen, i.e. can not send funds.
+    pub fn is_frozen(&self, who: &T::AccountId) -> bool {
+        self.frozen.contains(who)
+    }
+
+    // Get how much `spender` may still spend from the funds of `owner`.
+    pub fn allowance(&self, owner: &T::AccountId, spender: &T::AccountId) -> T::Balance {
+        *self
+            .allowances
+            .get(&(owner.clone(), spender.clone()))
+            .unwrap_or(&T::Balance::zero())
+    }
+
+    // Check that `spender` may spend `amount` from `owner`, and return the allowance left after it.
+    fn check_allowance(
+        &self,
+        owner: &T::AccountId,
+        spender: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<T::Balance, BalancesError> {
+        let allowance = self
+            .allowances
+            .get(&(owner.clone(), spender.clone()))
+            .ok_or(BalancesError::NoAllowance)?;
+        allowance
+            .checked_sub(&amount)
+            .ok_or(BalancesError::AllowanceTooLow)
+    }
+
+    // Store the allowance of `spender` over the funds of `owner`, removing the entry when it is zero.
+    fn write_allowance(&mut self, owner: T::AccountId, spender: T::AccountId, amount: T::Balance) {
+        if !self.transactions.is_empty() {
+            let key = (owner.clone(), spender.clone());
+            let previous = self.allowances.get(&key).copied();
+            self.journal.push(JournalEntry::Allowance(key, previous));
+        }
+        if amount.is_zero() {
+            self.allowances.remove(&(owner, spender));
+        } else {
+            self.allowances.insert((owner, spender), amount);
+        }
+    }
+
+    // Check that `amount` can be withdrawn from the free balance of `who` for `reason`, and
+    // return its free balance after the withdrawal.
+    fn check_withdraw(
+        &self,
+        who: &T::AccountId,
+        amount: T::Balance,
+        reason: WithdrawReason,
+    ) -> Result<T::Balance, BalancesError> {
+        if reason == WithdrawReason::Transfer && self.is_frozen(who) {
+            return Err(BalancesError::FrozenAccount);
+        }
+        let new_balance = self
+            .balance(who)
+            .checked_sub(&amount)
+            .ok_or(BalancesError::InsufficientFunds)?;
+        if reason != WithdrawReason::Slash {
+            self.check_locks(who, new_balance)?; // locked funds can only be slashed
+        }
+        Ok(new_balance)
+    }
+
+    // Check that `amount` can be deposited to the free balance of `who`, and return its free
+    // balance after the deposit.
+    fn check_deposit(
+        &self,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<T::Balance, BalancesError> {
+        let new_balance = self
+            .balance(who)
+            .checked_add(&amount)
+            .ok_or(BalancesError::Overflow)?;
+        self.check_existential_deposit(who, amount)?; // new accounts must end up existing
+        Ok(new_balance)
+    }
+
+    // Store the free balance of `who` after a checked withdrawal, reaping it like
+    // `write_balance_or_reap`. The balance is updated in place, so no key is cloned. Returns the
+    // new balance, which is zero for a reaped account.
+    fn write_withdraw(&mut self, who: &T::AccountId, new_balance: T::Balance) -> T::Balance {
+        self.note_balance(who);
+        let keep = !new_balance.is_zero()
+            && (new_balance >= T::EXISTENTIAL_DEPOSIT || self.is_kept_alive(who));
+        match self.balances.get_mut(who) {
+            Some(balance) if keep => {
+                *balance = new_balance;
+                new_balance
+            }
+            _ => {
+                self.balances.remove(who);
+                self.collect_dust(who, new_balance);
+                T::Balance::zero()
+            }
+        }
+    }
+
+    // Store the free balance of `who` after a checked deposit. The key is only cloned when `who`
+    // is a new account.
+    fn write_deposit(&mut self, who: &T::AccountId, new_balance: T::Balance) {
+        self.note_balance(who);
+        match self.balances.get_mut(who) {
+            Some(balance) => *balance = new_balance,
+            None => {
+                self.balances.insert(who.clone(), new_balance);
+            }
+        }
+    }
+
+    // Take `amount` out of the free balance of `who`, removing it from the total issuance. How
+    // the withdrawal is checked depends on its `reason`, see `WithdrawReason`. Funds withdrawn to
+    // be reserved are not burned: they move to the reserved balance and stay in the issuance.
+    // Other pallets use this, along with `deposit`, when `transfer` does not fit.
+    pub fn withdraw(
         &mut self,
-        caller: T::AccountId, // The account ID of the sender.
-        to: T::AccountId,     // The account ID of the receiver.
-        amount: T::Balance,   // The amount being sent to the receiver.
-    ) -> crate::support::DispatchResult {
-        let caller_balance = self.balance(&caller); // this is the balance of the caller
-        let to_balance = self.balance(&to); // this is the balance of the receiver
+        who: &T::AccountId,
+        amount: T::Balance,
+        reason: WithdrawReason,
+    ) -> Result<(), BalancesError> {
+        if reason == WithdrawReason::Reserve {
+            self.reserve(who, amount)?;
+            self.events.push(Event::Withdrawn {
+                who: who.clone(),
+                amount,
+                reason,
+            });
+            return Ok(());
+        }
+        let new_balance = self.check_withdraw(who, amount, reason)?;
+        // The issuance is the sum of all balances, so it is at least `amount` here.
+        let new_issuance = self
+            .total_issuance
+            .checked_sub(&amount)
+            .ok_or(BalancesError::IssuanceUnderflow)?;
 
-        let new_caller_balance = caller_balance // this is the new caller balance
-            .checked_sub(&amount) // this checks if the subtraction can actually happen
-            .ok_or("Not enough funds.")?; // calls an error if there is one
-        let new_to_balance = to_balance.checked_add(&amount).ok_or("Overflow")?;
+        self.note_totals();
+        self.total_issuance = new_issuance;
+        let new_balance = self.write_withdraw(who, new_balance);
+        self.notify_balance_changed(who, &new_balance);
+        self.events.push(Event::Withdrawn {
+            who: who.clone(),
+            amount,
+            reason,
+        });
+        Ok(())
+    }
 
-        self.balances.insert(caller, new_caller_balance); // the new balane for the caller
-        self.balances.insert(to, new_to_balance); // the new ba;ance for the receiver
+    // Add `amount` to the free balance of `who`, adding it to the total issuance. A new account
+    // must receive at least the existential deposit.
+    pub fn deposit(&mut self, who: &T::AccountId, amount: T::Balance) -> Result<(), BalancesError> {
+        let new_balance = self.check_deposit(who, amount)?;
+        let new_issuance = self
+            .total_issuance
+            .checked_add(&amount)
+            .ok_or(BalancesError::IssuanceOverflow)?;
 
+        self.note_totals();
+        self.total_issuance = new_issuance;
+        self.write_deposit(who, new_balance);
+        self.notify_balance_changed(who, &new_balance);
+        self.events.push(Event::Deposited {
+            who: who.clone(),
+            amount,
+        });
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    struct TestConfig;
+    // Move `amount` from `caller` to `to` without running the transfer hooks. Returns the new
+    // balances of both accounts, or `None` when sending to yourself left them unchanged.
+    fn apply_transfer(
+        &mut self,
+        caller: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<AppliedTransfer<T::Balance>, BalancesError> {
+        if self.is_frozen(caller) {
+            return Err(BalancesError::FrozenAccount);
+        }
+        if amount.is_zero() {
+            return Err(BalancesError::ZeroTransfer);
+        }
+        if amount < self.minimum_transfer {
+            return Err(BalancesError::BelowMinimum);
+        }
+        self.check_transfer_policy(caller, to)?; // both sides must allow the transfer
 
-    impl crate::system::Config for TestConfig {
-        type AccountId = String;
-        type BlockNumber = u32;
-        type Nonce = u32;
+        if caller == to {
+            // Sending to yourself changes nothing, but still needs the funds.
+            self.balance(caller)
+                .checked_sub(&amount)
+                .ok_or(BalancesError::InsufficientFunds)?;
+            return Ok(None);
+        }
+
+        // A transfer is a withdrawal from the caller and a deposit to the receiver, which leaves
+        // the issuance unchanged. Both are checked before either is written.
+        let new_caller_balance = self.check_withdraw(caller, amount, WithdrawReason::Transfer)?;
+        let new_spent = self.check_spending_limit(caller, amount)?;
+        let new_to_balance = self.check_deposit(to, amount)?;
+        self.write_deposit(to, new_to_balance);
+        let new_caller_balance = self.write_withdraw(caller, new_caller_balance);
+        // The receiver may be the dust collector, and have received the dust of the caller too.
+        let new_to_balance = self.balance(to);
+        if let Some(spent) = new_spent {
+            if !self.transactions.is_empty() {
+                let previous = self.spent_this_block.get(caller).copied();
+                self.journal
+                    .push(JournalEntry::Spent(caller.clone(), previous));
+            }
+            self.spent_this_block.insert(caller.clone(), spent);
+        }
+
+        Ok(Some((new_caller_balance, new_to_balance)))
     }
 
-    impl super::Config for TestConfig {
-        type Balance = u128;
+    // Start a transaction: the free and reserved balances, allowances, spending counters, totals
+    // and events written from now on can be undone with `rollback_transaction`. Transactions nest,
+    // each `start_transaction` must be matched by a commit or a rollback. Other storage is not
+    // journaled, so calls only write it once they can no longer fail. The transfer hooks are only
+    // notified once the outermost transaction commits, so they never see undone changes.
+    pub fn start_transaction(&mut self) {
+        self.transactions.push((
+            self.journal.len(),
+            self.events.len(),
+            self.pending_hooks.len(),
+        ));
     }
 
-    #[test]
-    fn init_balances() {
-        let mut balances = super::Pallet::<TestConfig>::new();
+    // Keep the writes of the innermost transaction. They can still be undone by rolling back an
+    // outer transaction. Does nothing when no transaction is open.
+    pub fn commit_transaction(&mut self) {
+        self.transactions.pop();
+        if self.transactions.is_empty() {
+            self.journal.clear();
+            for call in core::mem::take(&mut self.pending_hooks) {
+                match call {
+                    HookCall::Transfer(from, to, amount) => {
+                        self.hooks.on_transfer(&from, &to, &amount)
+                    }
+                    HookCall::BalanceChanged(who, new_free) => {
+                        self.hooks.on_balance_changed(&who, &new_free)
+                    }
+                }
+            }
+        }
+    }
 
-        assert_eq!(balances.balance(&"alice".to_string()), 0); // asserting that the balance of alice is 0 ( The assert_eq! macro in Rust is used for testing).
-        balances.set_balance(&"alice".to_string(), 100); // setting the balance of alice to 100.
-        assert_eq!(balances.balance(&"alice".to_string()), 100); // asserting that the balance of alice is 100.
-        assert_eq!(balances.balance(&"bob".to_string()), 0); // aserting that the balance of bob is 0.
+    // Undo the writes of the innermost transaction, restoring the exact values from before it
+    // started, and drop its hook notifications. Does nothing when no transaction is open.
+    pub fn rollback_transaction(&mut self) {
+        let Some((journal_len, events_len, pending_hooks_len)) = self.transactions.pop() else {
+            return;
+        };
+        self.events.truncate(events_len);
+        self.pending_hooks.truncate(pending_hooks_len);
+        let entries = self.journal.split_off(journal_len);
+        for entry in entries.into_iter().rev() {
+            match entry {
+                JournalEntry::Balance(who, previous) => {
+                    restore_entry(&mut self.balances, who, previous)
+                }
+                JournalEntry::Reserved(who, previous) => {
+                    restore_entry(&mut self.reserved, who, previous)
+                }
+                JournalEntry::Allowance(key, previous) => {
+                    restore_entry(&mut self.allowances, key, previous)
+                }
+                JournalEntry::Spent(who, previous) => {
+                    restore_entry(&mut self.spent_this_block, who, previous)
+                }
+                JournalEntry::Totals {
+                    total_issuance,
+                    collected_fees,
+                    collected_dust,
+                } => {
+                    self.total_issuance = total_issuance;
+                    self.collected_fees = collected_fees;
+                    self.collected_dust = collected_dust;
+                }
+            }
+        }
     }
-    #[test]
-    fn transfer_balance() {
-        let mut balances = super::Pallet::<TestConfig>::new();
 
-        assert_eq!(
-            balances.transfer("alice".to_string(), "bob".to_string(), 51), // Transfering 51 from alice to bob.
-            Err("Not enough funds.")
-        );
+    // Record the free balance of `who` before it is overwritten, if a transaction is open.
+    fn note_balance(&mut self, who: &T::AccountId) {
+        if !self.transactions.is_empty() {
+            let previous = self.balances.get(who).copied();
+            self.journal
+                .push(JournalEntry::Balance(who.clone(), previous));
+        }
+    }
 
-        balances.set_balance(&"alice".to_string(), 100); // Sets the balance to 100.
-        assert_eq!(
-            balances.transfer("alice".to_string(), "bob".to_string(), 51), // This tests a successful transfer of 51 units from "alice" to "bob",
-            Ok(()) // asserting that it returns Ok(()), indicating success.
-        );
-        assert_eq!(balances.balance(&"alice".to_string()), 49); // asserts that after the transfer, the balance of "alice" is reduced to 49.
-        assert_eq!(balances.balance(&"bob".to_string()), 51); // asserts that after the transfer, the balance of "bob" is increased to 51.
+    // Record the total issuance, collected fees and collected dust before one of them is
+    // overwritten, if a transaction is open.
+    fn note_totals(&mut self) {
+        if !self.transactions.is_empty() {
+            self.journal.push(JournalEntry::Totals {
+                total_issuance: self.total_issuance,
+                collected_fees: self.collected_fees,
+                collected_dust: self.collected_dust,
+            });
+        }
+    }
 
-        assert_eq!(
-            balances.transfer("alice".to_string(), "bob".to_string(), 51), // Tests another transfer attempt from "alice" to "bob" with insufficient funds,
-            Err("Not enough funds.") // expecting it to return an error.
+    // Check that `who` can send `amount` more in the current block without going over its
+    // spending limit. Returns what it will have spent in the block, or `None` without a limit.
+    fn check_spending_limit(
+        &self,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<Option<T::Balance>, BalancesError> {
+        let Some(limit) = self.spending_limits.get(who) else {
+            return Ok(None);
+        };
+        let spent = self
+            .spent_this_block(who)
+            .checked_add(&amount)
+            .ok_or(BalancesError::Overflow)?;
+        if spent > *limit {
+            return Err(BalancesError::SpendingLimitExceeded);
+        }
+        Ok(Some(spent))
+    }
+
+    // Get the spending limit of `who` per block, if it has one.
+    pub fn spending_limit(&self, who: &T::AccountId) -> Option<T::Balance> {
+        self.spending_limits.get(who).copied()
+    }
+
+    // Get how much `who` sent in the current block, counted only while it has a spending limit.
+    pub fn spent_this_block(&self, who: &T::AccountId) -> T::Balance {
+        *self
+            .spent_this_block
+            .get(who)
+            .unwrap_or(&T::Balance::zero())
+    }
+
+    // Destroy `amount` from the free balance of `from` without running the hooks, reducing the
+    // total issuance. Returns the new balance of `from`.
+    fn apply_burn(
+        &mut self,
+        from: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<T::Balance, BalancesError> {
+        let new_from_balance = self
+            .balance(from)
+            .checked_sub(&amount)
+            .ok_or(BalancesError::InsufficientFunds)?;
+        self.check_locks(from, new_from_balance)?;
+        // The issuance is the sum of all balances, so it is at least `amount` here.
+        let new_issuance = self
+            .total_issuance
+            .checked_sub(&amount)
+            .ok_or(BalancesError::IssuanceUnderflow)?;
+
+        self.note_totals();
+        self.total_issuance = new_issuance;
+        Ok(self.write_balance_or_reap(from.clone(), new_from_balance))
+    }
+
+    // Take the `tip` of a transfer from `caller`: it is sent to the block author, or burned when
+    // there is none. Like `apply_transfer`, the hooks are not run.
+    fn apply_tip(
+        &mut self,
+        caller: &T::AccountId,
+        tip: T::Balance,
+    ) -> Result<PaidTip<T::AccountId, T::Balance>, BalancesError> {
+        if tip.is_zero() {
+            return Ok(PaidTip::Nothing);
+        }
+        match self.block_author.clone() {
+            Some(author) => {
+                let new_balances = self.apply_transfer(caller, &author, tip)?;
+                Ok(PaidTip::ToAuthor(author, new_balances))
+            }
+            None => Ok(PaidTip::Burned(self.apply_burn(caller, tip)?)),
+        }
+    }
+
+    // Notify the transfer hooks of a transfer, or hold the notification back until the outermost
+    // transaction commits.
+    fn notify_transfer(&mut self, from: &T::AccountId, to: &T::AccountId, amount: &T::Balance) {
+        if self.transactions.is_empty() {
+            self.hooks.on_transfer(from, to, amount);
+        } else {
+            self.pending_hooks
+                .push(HookCall::Transfer(from.clone(), to.clone(), *amount));
+        }
+    }
+
+    // Notify the transfer hooks of the new free balance of `who`, or hold the notification back
+    // until the outermost transaction commits.
+    fn notify_balance_changed(&mut self, who: &T::AccountId, new_free: &T::Balance) {
+        if self.transactions.is_empty() {
+            self.hooks.on_balance_changed(who, new_free);
+        } else {
+            self.pending_hooks
+                .push(HookCall::BalanceChanged(who.clone(), *new_free));
+        }
+    }
+
+    // Get the listeners notified after balances change, e.g. to read the state they keep.
+    pub fn hooks(&self) -> &T::TransferHooks {
+        &self.hooks
+    }
+
+    // Notify the transfer hooks of a transfer which went through, with the new balances of both sides.
+    fn run_transfer_hooks(
+        &mut self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+        new_from_balance: T::Balance,
+        new_to_balance: T::Balance,
+    ) {
+        self.notify_transfer(from, to, &amount);
+        self.notify_balance_changed(from, &new_from_balance);
+        self.notify_balance_changed(to, &new_to_balance);
+    }
+
+    // Check that `caller` is the root account.
+    fn ensure_root(&self, caller: &T::AccountId) -> Result<(), BalancesError> {
+        if self.root_account.as_ref() != Some(caller) {
+            return Err(BalancesError::NotRoot);
+        }
+        Ok(())
+    }
+
+    // Get the total issuance, i.e. the sum of all balances.
+    pub fn total_issuance(&self) -> T::Balance {
+        self.total_issuance
+    }
+
+    // Get the balance of `who` in the asset `id`. Unknown assets and accounts have a zero balance.
+    pub fn balance_of(&self, id: T::AssetId, who: &T::AccountId) -> T::Balance {
+        *self
+            .asset_balances
+            .get(&(id, who.clone()))
+            .unwrap_or(&T::Balance::zero())
+    }
+
+    // Get the details of the asset `id`, if it exists.
+    pub fn asset(&self, id: T::AssetId) -> Option<&AssetDetails<T::AccountId, T::Balance>> {
+        self.assets.get(&id)
+    }
+
+    // Store the balance of `who` in the asset `id`, removing the entry when it is zero.
+    fn write_asset_balance(&mut self, id: T::AssetId, who: T::AccountId, amount: T::Balance) {
+        if amount.is_zero() {
+            self.asset_balances.remove(&(id, who));
+        } else {
+            self.asset_balances.insert((id, who), amount);
+        }
+    }
+
+    // Get the transfer policy of an account `who`, if it has one.
+    pub fn transfer_policy(&self, who: &T::AccountId) -> Option<&TransferPolicy<T::AccountId>> {
+        self.transfer_policies.get(who)
+    }
+
+    // Store the transfer policy of `who`. Setting `AllowAll` with an empty list removes the policy.
+    fn write_transfer_policy(
+        &mut self,
+        who: T::AccountId,
+        mode: TransferMode,
+        list: Vec<T::AccountId>,
+    ) -> Result<(), BalancesError> {
+        if list.len() > T::MAX_TRANSFER_POLICY_LEN {
+            return Err(BalancesError::PolicyListTooLong);
+        }
+        if mode == TransferMode::AllowAll && list.is_empty() {
+            self.transfer_policies.remove(&who);
+        } else {
+            self.transfer_policies
+                .insert(who, TransferPolicy { mode, list });
+        }
+        Ok(())
+    }
+
+    // Check that the policies of both `from` and `to` allow a transfer between them.
+    fn check_transfer_policy(
+        &self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+    ) -> Result<(), BalancesError> {
+        if let Some(policy) = self.transfer_policies.get(from) {
+            if !policy.allows(to) {
+                return Err(BalancesError::SenderRestricted);
+            }
+        }
+        if let Some(policy) = self.transfer_policies.get(to) {
+            if !policy.allows(from) {
+                return Err(BalancesError::RecipientRestricted);
+            }
+        }
+        Ok(())
+    }
+}
+#[macros::call]
+impl<T: Config> Pallet<T> {
+    /// Transfer `amount` from one account to another.
+    /// This function verifies that `from` has at least `amount` balance to transfer,
+    /// and that no mathematical overflows occur. We made everything generic and customizable.
+    ///
+    /// - `to`: The account ID of the receiver.
+    /// - `amount`: The amount being sent to the receiver.
+    pub fn transfer(
+        &mut self,
+        caller: T::AccountId, // The account ID of the sender.
+        to: T::AccountId,     // The account ID of the receiver.
+        amount: T::Balance,   // The amount being sent to the receiver.
+    ) -> Result<(), BalancesError> {
+        if let Some((new_caller_balance, new_to_balance)) =
+            self.apply_transfer(&caller, &to, amount)?
+        {
+            // The hooks only run once the transfer has fully succeeded.
+            self.run_transfer_hooks(&caller, &to, amount, new_caller_balance, new_to_balance);
+            self.events.push(Event::Transfer {
+                from: caller,
+                to,
+                amount,
+            });
+        }
+        Ok(())
+    }
+
+    /// Transfer `amount` from the caller to `to`, like `transfer`, with a `memo` recorded in the
+    /// emitted event, e.g. a payment reference.
+    ///
+    /// - `to`: The account ID of the receiver.
+    /// - `amount`: The amount being sent to the receiver.
+    /// - `memo`: A short note for the receiver, at most `MAX_MEMO_LEN` bytes.
+    pub fn transfer_with_memo(
+        &mut self,
+        caller: T::AccountId,
+        to: T::AccountId,
+        amount: T::Balance,
+        memo: Vec<u8>,
+    ) -> Result<(), BalancesError> {
+        if memo.len() > T::MAX_MEMO_LEN {
+            return Err(BalancesError::MemoTooLong);
+        }
+        if let Some((new_caller_balance, new_to_balance)) =
+            self.apply_transfer(&caller, &to, amount)?
+        {
+            self.run_transfer_hooks(&caller, &to, amount, new_caller_balance, new_to_balance);
+            self.events.push(Event::TransferWithMemo {
+                from: caller,
+                to,
+                amount,
+                memo,
+            });
+        }
+        Ok(())
+    }
+
+    /// Transfer `amount` from the caller to `to`, and pay a `tip` to the author of the current
+    /// block on top of it. The tip is sent like a transfer, so it must also reach the minimum
+    /// transfer. Without a block author, the tip is burned. Either both are paid, or the call
+    /// fails and no balance changes.
+    ///
+    /// - `to`: The account ID of the receiver.
+    /// - `amount`: The amount being sent to the receiver.
+    /// - `tip`: The amount paid to the block author, may be zero.
+    pub fn transfer_with_tip(
+        &mut self,
+        caller: T::AccountId,
+        to: T::AccountId,
+        amount: T::Balance,
+        tip: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.start_transaction();
+        let paid = self
+            .apply_transfer(&caller, &to, amount)
+            .and_then(|transferred| Ok((transferred, self.apply_tip(&caller, tip)?)));
+        let (transferred, tipped) = match paid {
+            Ok(paid) => {
+                self.commit_transaction();
+                paid
+            }
+            Err(error) => {
+                self.rollback_transaction(); // the transfer is undone
+                return Err(error);
+            }
+        };
+
+        // The hooks only run once both the transfer and the tip have succeeded.
+        if let Some((new_caller_balance, new_to_balance)) = transferred {
+            self.run_transfer_hooks(&caller, &to, amount, new_caller_balance, new_to_balance);
+            self.events.push(Event::Transfer {
+                from: caller.clone(),
+                to,
+                amount,
+            });
+        }
+        match tipped {
+            PaidTip::Nothing | PaidTip::ToAuthor(_, None) => {}
+            PaidTip::ToAuthor(author, Some((new_caller_balance, new_author_balance))) => {
+                self.run_transfer_hooks(
+                    &caller,
+                    &author,
+                    tip,
+                    new_caller_balance,
+                    new_author_balance,
+                );
+                self.events.push(Event::Transfer {
+                    from: caller,
+                    to: author,
+                    amount: tip,
+                });
+            }
+            PaidTip::Burned(new_caller_balance) => {
+                self.notify_balance_changed(&caller, &new_caller_balance);
+                self.events.push(Event::Burned {
+                    who: caller,
+                    amount: tip,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Send several transfers from the caller in one call. Either every transfer succeeds, or
+    /// the call fails and no balance changes. Each transfer sees the balances left by the
+    /// transfers before it.
+    ///
+    /// - `recipients`: The receivers, each with the amount sent to them.
+    pub fn transfer_batch(
+        &mut self,
+        caller: T::AccountId,
+        recipients: Vec<(T::AccountId, T::Balance)>,
+    ) -> Result<(), BalancesError> {
+        self.start_transaction();
+        let mut applied = Vec::new();
+        for (to, amount) in recipients {
+            match self.apply_transfer(&caller, &to, amount) {
+                Ok(new_balances) => applied.push((to, amount, new_balances)),
+                Err(error) => {
+                    // The transfers that went through before the failing one are undone.
+                    self.rollback_transaction();
+                    return Err(error);
+                }
+            }
+        }
+        self.commit_transaction();
+
+        // The hooks only run once the whole batch has succeeded.
+        for (to, amount, new_balances) in applied {
+            if let Some((new_caller_balance, new_to_balance)) = new_balances {
+                self.run_transfer_hooks(&caller, &to, amount, new_caller_balance, new_to_balance);
+                self.events.push(Event::Transfer {
+                    from: caller.clone(),
+                    to,
+                    amount,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Split `total` between the recipients in proportion to their integer shares, e.g. for a
+    /// payroll. The remainder of the division goes to the first recipient, so exactly `total` is
+    /// spent. Like `transfer_batch`, either every transfer succeeds or nothing changes.
+    ///
+    /// - `total`: The amount split between the recipients.
+    /// - `shares`: The recipients, each with their non-zero share weight.
+    pub fn transfer_shares(
+        &mut self,
+        caller: T::AccountId,
+        total: T::Balance,
+        shares: Vec<(T::AccountId, u32)>,
+    ) -> Result<(), BalancesError> {
+        if shares.is_empty() {
+            return Err(BalancesError::NoRecipients);
+        }
+        if shares.iter().any(|(_, share)| *share == 0) {
+            return Err(BalancesError::ZeroShare);
+        }
+        if self.balance(&caller) < total {
+            return Err(BalancesError::InsufficientFunds);
+        }
+        let total_shares = shares
+            .iter()
+            .try_fold(0u32, |sum, (_, share)| sum.checked_add(*share))
+            .ok_or(BalancesError::Overflow)?;
+        let total_shares = T::Balance::from(total_shares);
+        // Dividing first keeps the products small, so large totals do not overflow: the rest of
+        // the division is below `total_shares`, and its product with a share fits in 64 bits.
+        let per_share = total
+            .checked_div(&total_shares)
+            .ok_or(BalancesError::Overflow)?;
+        let rest = per_share
+            .checked_mul(&total_shares)
+            .and_then(|spent| total.checked_sub(&spent))
+            .ok_or(BalancesError::Overflow)?;
+
+        let mut recipients = Vec::with_capacity(shares.len());
+        let mut remainder = total;
+        for (to, share) in shares {
+            let share = T::Balance::from(share);
+            let amount = rest
+                .checked_mul(&share)
+                .and_then(|product| product.checked_div(&total_shares))
+                .zip(per_share.checked_mul(&share))
+                .and_then(|(rest_amount, amount)| amount.checked_add(&rest_amount))
+                .ok_or(BalancesError::Overflow)?;
+            // The amounts add up to at most `total`, since they are rounded down.
+            remainder = remainder
+                .checked_sub(&amount)
+                .ok_or(BalancesError::Overflow)?;
+            recipients.push((to, amount));
+        }
+        if let Some((_, amount)) = recipients.first_mut() {
+            *amount = amount
+                .checked_add(&remainder)
+                .ok_or(BalancesError::Overflow)?;
+        }
+
+        // Recipients whose share rounds down to nothing receive no transfer.
+        recipients.retain(|(_, amount)| !amount.is_zero());
+        self.transfer_batch(caller, recipients)
+    }
+
+    /// Transfer the entire spendable balance of the caller to `to`, emptying the account unless
+    /// some of its funds are locked. An empty account makes this a successful no-op.
+    ///
+    /// - `to`: The account ID of the receiver.
+    pub fn transfer_all(
+        &mut self,
+        caller: T::AccountId,
+        to: T::AccountId,
+    ) -> Result<(), BalancesError> {
+        // The amount is read and sent in the same call, so nothing can change it in between.
+        let amount = self.spendable_balance(&caller);
+        if amount.is_zero() {
+            return Ok(());
+        }
+        self.transfer(caller, to, amount)
+    }
+
+    /// Transfer `amount` from `from` to `to` on behalf of the root account, for administrative
+    /// recovery. Unlike `transfer`, it ignores the freeze, transfer policy, spending limit and
+    /// minimum transfer of `from`.
+    ///
+    /// - `from`: The account ID of the sender.
+    /// - `to`: The account ID of the receiver.
+    /// - `amount`: The amount being sent to the receiver.
+    pub fn force_transfer(
+        &mut self,
+        caller: T::AccountId,
+        from: T::AccountId,
+        to: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        // The freeze, transfer policy, spending limit and minimum transfer are restrictions of the
+        // users, which root overrides. Locks and the existential deposit still apply.
+        let new_from_balance = self
+            .balance(&from)
+            .checked_sub(&amount)
+            .ok_or(BalancesError::InsufficientFunds)?;
+        self.check_locks(&from, new_from_balance)?;
+        if from == to {
+            return Ok(());
+        }
+        let new_to_balance = self.check_deposit(&to, amount)?;
+        self.write_deposit(&to, new_to_balance);
+        let new_from_balance = self.write_withdraw(&from, new_from_balance);
+        // The receiver may be the dust collector, and have received the dust of the sender too.
+        let new_to_balance = self.balance(&to);
+        self.run_transfer_hooks(&from, &to, amount, new_from_balance, new_to_balance);
+        self.events.push(Event::Transfer { from, to, amount });
+        Ok(())
+    }
+
+    /// Freeze the account `who`, so it can not send funds until it is thawed. It can still
+    /// receive funds. Only the root account can freeze accounts.
+    ///
+    /// - `who`: The account being frozen.
+    pub fn freeze(&mut self, caller: T::AccountId, who: T::AccountId) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        self.frozen.insert(who);
+        Ok(())
+    }
+
+    /// Thaw the account `who`, so it can send funds again. Only the root account can thaw accounts.
+    ///
+    /// - `who`: The account being thawed.
+    pub fn thaw(&mut self, caller: T::AccountId, who: T::AccountId) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        self.frozen.remove(&who);
+        Ok(())
+    }
+
+    /// Destroy up to `amount` of the free balance of `who`, see `slash`. Only the root account
+    /// can slash accounts.
+    ///
+    /// - `who`: The account being slashed.
+    /// - `amount`: The most that is destroyed.
+    pub fn slash_account(
+        &mut self,
+        caller: T::AccountId,
+        who: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        self.slash(&who, amount);
+        Ok(())
+    }
+
+    /// Set the free balance of `who` to `amount`, adjusting the total issuance by the difference.
+    /// Only the root account can set balances.
+    ///
+    /// - `who`: The account whose balance is set.
+    /// - `amount`: The new free balance.
+    pub fn force_set_balance(
+        &mut self,
+        caller: T::AccountId,
+        who: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        self.total_issuance
+            .checked_sub(&self.balance(&who))
+            .and_then(|issuance| issuance.checked_add(&amount))
+            .ok_or(BalancesError::IssuanceOverflow)?;
+        if !amount.is_zero() && amount < T::EXISTENTIAL_DEPOSIT && !self.is_kept_alive(&who) {
+            return Err(BalancesError::BelowExistentialDeposit);
+        }
+        self.set_balance(&who, amount); // The issuance was checked above, so this can not panic.
+        Ok(())
+    }
+
+    /// Set the smallest amount a transfer can send. Transfers of zero are always rejected.
+    /// Only the root account can set the minimum.
+    ///
+    /// - `minimum`: The new minimum transfer.
+    pub fn set_minimum_transfer(
+        &mut self,
+        caller: T::AccountId,
+        minimum: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        self.minimum_transfer = minimum;
+        Ok(())
+    }
+
+    /// Send `schedule.locked` from the caller to `to`, where it unlocks following the `schedule`.
+    /// The receiver can not have another vesting schedule.
+    ///
+    /// - `to`: The account ID of the receiver.
+    /// - `schedule`: How the sent funds unlock over the blocks.
+    pub fn vested_transfer(
+        &mut self,
+        caller: T::AccountId,
+        to: T::AccountId,
+        schedule: VestingSchedule<T::Balance, T::BlockNumber>,
+    ) -> Result<(), BalancesError> {
+        if schedule.per_block.is_zero() {
+            return Err(BalancesError::VestingNeverUnlocks);
+        }
+        if self.vesting.contains_key(&to) {
+            return Err(BalancesError::VestingScheduleExists);
+        }
+        if caller == to {
+            return Err(BalancesError::VestToSelf);
+        }
+        self.transfer(caller, to.clone(), schedule.locked)?;
+        self.vesting.insert(to.clone(), schedule);
+        self.update_vesting_lock(&to);
+        Ok(())
+    }
+
+    /// Unlock the funds of the caller's vesting schedule which have vested by the current block.
+    pub fn vest(&mut self, caller: T::AccountId) -> Result<(), BalancesError> {
+        if !self.vesting.contains_key(&caller) {
+            return Err(BalancesError::NoVestingSchedule);
+        }
+        self.update_vesting_lock(&caller);
+        Ok(())
+    }
+
+    /// Allow `spender` to spend up to `amount` of the caller's funds with `transfer_from`.
+    /// This replaces any previous allowance, and an amount of zero removes it.
+    ///
+    /// - `spender`: The account allowed to spend the funds.
+    /// - `amount`: The maximum amount the spender can spend.
+    pub fn approve(
+        &mut self,
+        caller: T::AccountId,
+        spender: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.write_allowance(caller, spender, amount);
+        Ok(())
+    }
+
+    /// Transfer `amount` from `from` to `to`, spending the allowance `from` gave to the caller.
+    /// The allowance is only used up if the transfer succeeds, and `to` can not be `from`.
+    ///
+    /// - `from`: The account whose funds are sent.
+    /// - `to`: The account ID of the receiver.
+    /// - `amount`: The amount being sent to the receiver.
+    pub fn transfer_from(
+        &mut self,
+        caller: T::AccountId,
+        from: T::AccountId,
+        to: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        // Nothing would move, so no allowance is used up for it either.
+        if from == to {
+            return Err(BalancesError::SelfTransfer);
+        }
+        let new_allowance = self.check_allowance(&from, &caller, amount)?;
+        self.transfer(from.clone(), to, amount)?;
+        self.write_allowance(from, caller, new_allowance);
+        Ok(())
+    }
+
+    /// Burn `amount` from the free balance of `from`, spending the allowance `from` gave to the
+    /// caller, like `transfer_from`. The root account can burn from any account without an
+    /// allowance. The allowance is only used up if the burn succeeds.
+    ///
+    /// - `from`: The account whose funds are burned.
+    /// - `amount`: The amount being burned.
+    pub fn burn_from(
+        &mut self,
+        caller: T::AccountId,
+        from: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        let new_allowance = if self.ensure_root(&caller).is_ok() {
+            None
+        } else {
+            Some(self.check_allowance(&from, &caller, amount)?)
+        };
+        let new_from_balance = self.apply_burn(&from, amount)?;
+        if let Some(new_allowance) = new_allowance {
+            self.write_allowance(from.clone(), caller, new_allowance);
+        }
+        self.notify_balance_changed(&from, &new_from_balance);
+        self.events.push(Event::Burned { who: from, amount });
+        Ok(())
+    }
+
+    /// Limit how much the caller can send per block, to limit the damage if its key is stolen.
+    /// Transfers which would exceed the limit within a block are rejected.
+    ///
+    /// - `limit`: The most the caller can send in a block.
+    pub fn set_spending_limit(
+        &mut self,
+        caller: T::AccountId,
+        limit: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.spending_limits.insert(caller, limit);
+        Ok(())
+    }
+
+    /// Remove the spending limit of the caller.
+    pub fn clear_spending_limit(&mut self, caller: T::AccountId) -> Result<(), BalancesError> {
+        self.spending_limits.remove(&caller);
+        self.spent_this_block.remove(&caller);
+        Ok(())
+    }
+
+    /// Set the transfer policy of the caller, restricting who it can send to and receive from.
+    /// Setting `AllowAll` with an empty list removes the policy.
+    ///
+    /// - `mode`: How the list of counterparties is used.
+    /// - `list`: The listed counterparties, at most `MAX_TRANSFER_POLICY_LEN` of them.
+    pub fn set_transfer_policy(
+        &mut self,
+        caller: T::AccountId,
+        mode: TransferMode,
+        list: Vec<T::AccountId>,
+    ) -> Result<(), BalancesError> {
+        self.write_transfer_policy(caller, mode, list)
+    }
+
+    /// Impose a transfer policy on the account `who`, like `set_transfer_policy` sent by `who`.
+    /// Only the root account can impose policies.
+    ///
+    /// - `who`: The account whose transfer policy is set.
+    /// - `mode`: How the list of counterparties is used.
+    /// - `list`: The listed counterparties, at most `MAX_TRANSFER_POLICY_LEN` of them.
+    pub fn force_set_transfer_policy(
+        &mut self,
+        caller: T::AccountId,
+        who: T::AccountId,
+        mode: TransferMode,
+        list: Vec<T::AccountId>,
+    ) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        self.write_transfer_policy(who, mode, list)
+    }
+
+    /// Create `amount` new tokens in the account `to`, increasing the total issuance.
+    /// Only the root account can mint.
+    ///
+    /// - `to`: The account receiving the new tokens.
+    /// - `amount`: The amount of tokens created.
+    pub fn mint(
+        &mut self,
+        caller: T::AccountId,
+        to: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        self.ensure_root(&caller)?;
+        // All checks happen before any write, so a failed mint changes nothing.
+        let to_balance = self.balance(&to);
+        let new_to_balance = to_balance
+            .checked_add(&amount)
+            .ok_or(BalancesError::BalanceOverflow)?;
+        self.check_existential_deposit(&to, amount)?;
+        let new_issuance = self
+            .total_issuance
+            .checked_add(&amount)
+            .ok_or(BalancesError::IssuanceOverflow)?;
+
+        self.write_balance(to.clone(), new_to_balance);
+        self.note_totals();
+        self.total_issuance = new_issuance;
+        self.notify_balance_changed(&to, &new_to_balance);
+        self.events.push(Event::Minted { who: to, amount });
+
+        Ok(())
+    }
+
+    /// Destroy `amount` tokens of the account `from`, decreasing the total issuance.
+    /// Accounts can only burn their own tokens.
+    ///
+    /// - `from`: The account whose tokens are destroyed.
+    /// - `amount`: The amount of tokens destroyed.
+    pub fn burn(
+        &mut self,
+        caller: T::AccountId,
+        from: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        if caller != from {
+            return Err(BalancesError::NotOwnFunds);
+        }
+        let new_from_balance = self.apply_burn(&from, amount)?;
+        self.notify_balance_changed(&from, &new_from_balance);
+        self.events.push(Event::Burned { who: from, amount });
+
+        Ok(())
+    }
+
+    /// Create a new asset with the id `id`, owned by the caller. Only the owner can mint it.
+    ///
+    /// - `id`: The id of the new asset.
+    pub fn create_asset(
+        &mut self,
+        caller: T::AccountId,
+        id: T::AssetId,
+    ) -> Result<(), BalancesError> {
+        if self.assets.contains_key(&id) {
+            return Err(BalancesError::AssetAlreadyExists);
+        }
+        let details = AssetDetails {
+            owner: caller,
+            total_issuance: T::Balance::zero(),
+        };
+        self.assets.insert(id, details);
+        Ok(())
+    }
+
+    /// Create `amount` new tokens of the asset `id` in the account `to`.
+    /// Only the creator of the asset can mint it.
+    ///
+    /// - `id`: The asset being minted.
+    /// - `to`: The account receiving the new tokens.
+    /// - `amount`: The amount of tokens created.
+    pub fn mint_asset(
+        &mut self,
+        caller: T::AccountId,
+        id: T::AssetId,
+        to: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        let details = self.assets.get(&id).ok_or(BalancesError::UnknownAsset)?;
+        if details.owner != caller {
+            return Err(BalancesError::NotAssetCreator);
+        }
+        let new_issuance = details
+            .total_issuance
+            .checked_add(&amount)
+            .ok_or(BalancesError::IssuanceOverflow)?;
+        let new_to_balance = self
+            .balance_of(id, &to)
+            .checked_add(&amount)
+            .ok_or(BalancesError::BalanceOverflow)?;
+
+        self.write_asset_balance(id, to, new_to_balance);
+        if let Some(details) = self.assets.get_mut(&id) {
+            details.total_issuance = new_issuance;
+        }
+        Ok(())
+    }
+
+    /// Transfer `amount` of the asset `id` from the caller to `to`.
+    ///
+    /// - `id`: The asset being sent.
+    /// - `to`: The account ID of the receiver.
+    /// - `amount`: The amount being sent to the receiver.
+    pub fn transfer_asset(
+        &mut self,
+        caller: T::AccountId,
+        id: T::AssetId,
+        to: T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), BalancesError> {
+        if !self.assets.contains_key(&id) {
+            return Err(BalancesError::UnknownAsset);
+        }
+        let new_caller_balance = self
+            .balance_of(id, &caller)
+            .checked_sub(&amount)
+            .ok_or(BalancesError::InsufficientFunds)?;
+        if caller == to {
+            return Ok(()); // Sending to yourself changes nothing, but still needs the funds.
+        }
+        let new_to_balance = self
+            .balance_of(id, &to)
+            .checked_add(&amount)
+            .ok_or(BalancesError::Overflow)?;
+
+        self.write_asset_balance(id, caller, new_caller_balance);
+        self.write_asset_balance(id, to, new_to_balance);
+        Ok(())
+    }
+}
+
+// The initial balances of the chain, applied before any block executes.
+pub struct GenesisConfig<T: Config> {
+    pub balances: Vec<(T::AccountId, T::Balance)>,
+    pub minimum_transfer: T::Balance, // The smallest amount a transfer can send.
+}
+
+impl<T: Config> GenesisConfig<T> {
+    // Check the genesis values against the `Config`, like `CheckConfig` does for the constants.
+    pub fn check_config(&self) -> Vec<(ConfigSeverity, &'static str)> {
+        let mut warnings = vec![];
+        if !self.minimum_transfer.is_zero() && self.minimum_transfer < T::EXISTENTIAL_DEPOSIT {
+            warnings.push((
+                ConfigSeverity::Warning,
+                "minimum transfer is below the existential deposit, which new accounts need anyway",
+            ));
+        }
+        warnings
+    }
+
+    // Write the initial balances into an empty `pallet` and set the total issuance to their sum,
+    // along with the minimum transfer.
+    // Fails without changing anything if the pallet already holds funds, an account is listed
+    // twice, a balance is below the existential deposit, or the sum overflows.
+    pub fn build(&self, pallet: &mut Pallet<T>) -> Result<(), BalancesError> {
+        if !pallet.total_issuance.is_zero() {
+            return Err(BalancesError::GenesisNotEmpty);
+        }
+        let mut seen = BTreeSet::new();
+        let mut total_issuance = T::Balance::zero();
+        for (who, amount) in &self.balances {
+            if !seen.insert(who) {
+                return Err(BalancesError::DuplicateGenesisAccount);
+            }
+            if *amount < T::EXISTENTIAL_DEPOSIT {
+                return Err(BalancesError::BelowExistentialDeposit);
+            }
+            total_issuance = total_issuance
+                .checked_add(amount)
+                .ok_or(BalancesError::IssuanceOverflow)?;
+        }
+
+        for (who, amount) in &self.balances {
+            pallet.write_balance(who.clone(), *amount);
+        }
+        pallet.note_totals();
+        pallet.total_issuance = total_issuance;
+        pallet.minimum_transfer = self.minimum_transfer;
+        Ok(())
+    }
+}
+
+// A default pallet is an empty one, as created by `new`.
+impl<T: Config> Default for Pallet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Other pallets reach the balances pallet through the `Currency` trait. Every operation is checked
+// like the matching call sent by the account whose funds are moved.
+impl<T: Config> Currency<T::AccountId> for Pallet<T> {
+    type Balance = T::Balance;
+
+    fn free_balance(&self, who: &T::AccountId) -> T::Balance {
+        self.balance(who)
+    }
+
+    fn transfer(
+        &mut self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> crate::support::DispatchResult {
+        Pallet::transfer(self, from.clone(), to.clone(), amount).map_err(Into::into)
+    }
+
+    fn withdraw(
+        &mut self,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> crate::support::DispatchResult {
+        // Other pallets withdraw to charge for what they provide, like a fee.
+        Pallet::withdraw(self, who, amount, WithdrawReason::Fee).map_err(Into::into)
+    }
+
+    fn deposit(
+        &mut self,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> crate::support::DispatchResult {
+        Pallet::deposit(self, who, amount).map_err(Into::into)
+    }
+
+    fn reserve(
+        &mut self,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> crate::support::DispatchResult {
+        Pallet::reserve(self, who, amount).map_err(Into::into)
+    }
+
+    fn unreserve(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+        Pallet::unreserve(self, who, amount)
+    }
+
+    fn repatriate_reserved(
+        &mut self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> crate::support::DispatchResult {
+        Pallet::repatriate_reserved(self, from, to, amount).map_err(Into::into)
+    }
+}
+
+// The runtime collects the events of the balances pallet after each extrinsic.
+impl<T: Config> TakeEvents for Pallet<T> {
+    type Event = Event<T::AccountId, T::Balance>;
+
+    fn take_events(&mut self) -> Vec<Self::Event> {
+        core::mem::take(&mut self.events)
+    }
+}
+
+// The balances pallet keeps track of the block number, which vesting depends on.
+// The tips of a block are paid to its author, and the spending limits start over.
+impl<T: Config> OnBlockStart<T::BlockNumber, T::AccountId> for Pallet<T> {
+    fn on_block_start(&mut self, block_number: T::BlockNumber, author: Option<&T::AccountId>) {
+        self.block_number = block_number;
+        self.block_author = author.cloned();
+        self.spent_this_block.clear(); // spending limits apply per block
+    }
+}
+
+// The runtime undoes failed calls through the journal of the balances pallet.
+impl<T: Config> Transactional for Pallet<T> {
+    fn start_transaction(&mut self) {
+        Pallet::start_transaction(self)
+    }
+
+    fn commit_transaction(&mut self) {
+        Pallet::commit_transaction(self)
+    }
+
+    fn rollback_transaction(&mut self) {
+        Pallet::rollback_transaction(self)
+    }
+}
+
+// The integrity checks of the balances pallet.
+impl<T: Config> TryState<T::BlockNumber> for Pallet<T> {
+    fn try_state(&self, _block_number: T::BlockNumber) -> Result<(), &'static str> {
+        // Zero balances are never stored, see `write_balance`.
+        if self.balances.values().any(|balance| balance.is_zero()) {
+            return Err("zero balance stored");
+        }
+        // Every call closes the transactions it starts.
+        if !self.transactions.is_empty() {
+            return Err("transaction left open");
+        }
+        if self.reserved.values().any(|balance| balance.is_zero()) {
+            return Err("zero reserved balance stored");
+        }
+        // Accounts below the existential deposit are reaped, unless they are kept alive.
+        if self
+            .balances
+            .iter()
+            .any(|(who, balance)| *balance < T::EXISTENTIAL_DEPOSIT && !self.is_kept_alive(who))
+        {
+            return Err("balance below existential deposit stored");
+        }
+        // The total issuance is the sum of all free and reserved balances, and the collected fees.
+        let sum = self
+            .balances
+            .values()
+            .chain(self.reserved.values())
+            .chain(core::iter::once(&self.collected_fees))
+            .try_fold(T::Balance::zero(), |sum, balance| sum.checked_add(balance))
+            .ok_or("sum of balances overflows")?;
+        if sum != self.total_issuance {
+            return Err("total issuance does not match the sum of balances");
+        }
+        // The same holds for each asset, whose balances are only stored for existing assets.
+        if self
+            .asset_balances
+            .values()
+            .any(|balance| balance.is_zero())
+        {
+            return Err("zero asset balance stored");
+        }
+        for (id, details) in &self.assets {
+            let sum = self
+                .asset_balances
+                .iter()
+                .filter(|((asset, _), _)| asset == id)
+                .try_fold(T::Balance::zero(), |sum, (_, balance)| {
+                    sum.checked_add(balance)
+                })
+                .ok_or("sum of asset balances overflows")?;
+            if sum != details.total_issuance {
+                return Err("asset issuance does not match the sum of balances");
+            }
+        }
+        if self
+            .asset_balances
+            .keys()
+            .any(|(asset, _)| !self.assets.contains_key(asset))
+        {
+            return Err("balance stored for an unknown asset");
+        }
+        Ok(())
+    }
+}
+
+// The configuration checks of the balances pallet.
+impl<T: Config> CheckConfig for Pallet<T> {
+    fn check_config() -> Vec<(ConfigSeverity, &'static str)> {
+        let mut warnings = vec![];
+        // Without an existential deposit no account is ever reaped, so there is no dust to collect.
+        if T::EXISTENTIAL_DEPOSIT.is_zero() && T::DustCollector::get().is_some() {
+            warnings.push((
+                ConfigSeverity::Error,
+                "dust collector set, but the existential deposit is zero so no account is reaped",
+            ));
+        }
+        if T::TRANSACTION_FEE > T::EXISTENTIAL_DEPOSIT {
+            warnings.push((
+                ConfigSeverity::Warning,
+                "transaction fee is above the existential deposit, accounts holding it can not pay",
+            ));
+        }
+        if T::MAX_TRANSFER_POLICY_LEN == 0 {
+            warnings.push((
+                ConfigSeverity::Warning,
+                "transfer policy lists can not hold any account",
+            ));
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BalancesError, Event, LockIdentifier, TransferMode, VestingSchedule, WithdrawReason,
+    };
+    use crate::support::{
+        CheckConfig, ConfigSeverity, Currency, Get, OnBalanceChanged, OnBlockStart, OnTransfer,
+        TakeEvents, TryState,
+    };
+    use core::fmt::Debug;
+    use core::marker::PhantomData;
+
+    // The config of every test. The existential deposit, the dust collector, the transfer hooks
+    // and the transaction fee can be overridden, while the rest is shared.
+    struct BaseConfig<
+        const EXISTENTIAL_DEPOSIT: u128 = 0,
+        DustCollector = (),
+        TransferHooks = (),
+        const TRANSACTION_FEE: u128 = 0,
+    >(PhantomData<(DustCollector, TransferHooks)>);
+
+    impl<
+            const EXISTENTIAL_DEPOSIT: u128,
+            DustCollector,
+            TransferHooks,
+            const TRANSACTION_FEE: u128,
+        > crate::system::Config
+        for BaseConfig<EXISTENTIAL_DEPOSIT, DustCollector, TransferHooks, TRANSACTION_FEE>
+    {
+        type AccountId = String;
+        type BlockNumber = u32;
+        type Nonce = u32;
+        const IDEMPOTENCY_WINDOW: u32 = 0;
+        type RuntimeEvent = ();
+    }
+
+    impl<
+            const EXISTENTIAL_DEPOSIT: u128,
+            DustCollector,
+            TransferHooks,
+            const TRANSACTION_FEE: u128,
+        > super::Config
+        for BaseConfig<EXISTENTIAL_DEPOSIT, DustCollector, TransferHooks, TRANSACTION_FEE>
+    where
+        DustCollector: Get<Option<String>>,
+        TransferHooks: OnTransfer<String, u128> + OnBalanceChanged<String, u128> + Default + Debug,
+    {
+        type Balance = u128;
+        const EXISTENTIAL_DEPOSIT: u128 = EXISTENTIAL_DEPOSIT;
+        type DustCollector = DustCollector;
+        type TransferHooks = TransferHooks;
+        const MAX_TRANSFER_POLICY_LEN: usize = 2;
+        type AssetId = u32;
+        const TRANSACTION_FEE: u128 = TRANSACTION_FEE;
+        const MAX_MEMO_LEN: usize = 4;
+    }
+
+    // No existential deposit, no dust collector and no hooks.
+    type TestConfig = BaseConfig;
+
+    #[test]
+    fn init_balances() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+
+        assert_eq!(balances.balance(&"alice".to_string()), 0); // asserting that the balance of alice is 0 ( The assert_eq! macro in Rust is used for testing).
+        balances.set_balance(&"alice".to_string(), 100); // setting the balance of alice to 100.
+        assert_eq!(balances.balance(&"alice".to_string()), 100); // asserting that the balance of alice is 100.
+        assert_eq!(balances.balance(&"bob".to_string()), 0); // aserting that the balance of bob is 0.
+    }
+    #[test]
+    fn transfer_balance() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 51), // Transfering 51 from alice to bob.
+            Err(BalancesError::InsufficientFunds)
+        );
+
+        balances.set_balance(&"alice".to_string(), 100); // Sets the balance to 100.
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 51), // This tests a successful transfer of 51 units from "alice" to "bob",
+            Ok(()) // asserting that it returns Ok(()), indicating success.
+        );
+        assert_eq!(balances.balance(&"alice".to_string()), 49); // asserts that after the transfer, the balance of "alice" is reduced to 49.
+        assert_eq!(balances.balance(&"bob".to_string()), 51); // asserts that after the transfer, the balance of "bob" is increased to 51.
+
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 51), // Tests another transfer attempt from "alice" to "bob" with insufficient funds,
+            Err(BalancesError::InsufficientFunds) // expecting it to return an error.
+        );
+    }
+
+    #[test]
+    fn call_docs() {
+        let docs = super::Call::<TestConfig>::docs();
+        let (_, doc) = docs
+            .iter()
+            .find(|(name, _)| *name == "transfer")
+            .expect("transfer is a call");
+        assert!(doc.starts_with("Transfer `amount` from one account to another.")); // The doc comment is captured.
+
+        let args_docs = super::Call::<TestConfig>::args_docs("transfer");
+        assert_eq!(
+            args_docs,
+            &[
+                ("to", "The account ID of the receiver."),
+                ("amount", "The amount being sent to the receiver."),
+            ]
+        ); // Each argument doc is taken from its line in the doc comment.
+        assert!(super::Call::<TestConfig>::args_docs("unknown").is_empty()); // Unknown calls have no docs.
+    }
+
+    // Records every hook invocation so tests can check the order and arguments.
+    mod hooks {
+        use crate::support::{OnBalanceChanged, OnTransfer};
+        use std::cell::RefCell;
+
+        thread_local! {
+            pub static CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        #[derive(Debug, Default)]
+        pub struct First;
+        #[derive(Debug, Default)]
+        pub struct Second;
+
+        impl OnTransfer<String, u128> for First {
+            fn on_transfer(&mut self, from: &String, to: &String, amount: &u128) {
+                CALLS.with(|c| {
+                    c.borrow_mut()
+                        .push(format!("first: {} -> {}: {}", from, to, amount))
+                });
+            }
+        }
+        impl OnBalanceChanged<String, u128> for First {
+            fn on_balance_changed(&mut self, who: &String, new_free: &u128) {
+                CALLS.with(|c| {
+                    c.borrow_mut()
+                        .push(format!("first: {} = {}", who, new_free))
+                });
+            }
+        }
+        impl OnTransfer<String, u128> for Second {
+            fn on_transfer(&mut self, from: &String, to: &String, amount: &u128) {
+                CALLS.with(|c| {
+                    c.borrow_mut()
+                        .push(format!("second: {} -> {}: {}", from, to, amount))
+                });
+            }
+        }
+        impl OnBalanceChanged<String, u128> for Second {
+            fn on_balance_changed(&mut self, _who: &String, _new_free: &u128) {}
+        }
+
+        pub fn take() -> Vec<String> {
+            CALLS.with(|c| c.borrow_mut().drain(..).collect())
+        }
+    }
+
+    type HookedConfig = BaseConfig<0, (), (hooks::First, hooks::Second)>; // Two listeners, called in this order.
+
+    #[test]
+    fn transfer_hooks() {
+        let mut balances = super::Pallet::<HookedConfig>::new();
+        balances.set_balance(&"alice".to_string(), 100);
+        assert_eq!(hooks::take(), vec!["first: alice = 100"]); // Setting a balance notifies the change.
+
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 30),
+            Ok(())
+        );
+        assert_eq!(
+            hooks::take(),
+            vec![
+                "first: alice -> bob: 30",
+                "second: alice -> bob: 30",
+                "first: alice = 70",
+                "first: bob = 30",
+            ]
+        ); // Both hooks see the transfer in registration order, then the new balances.
+
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 71),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert!(hooks::take().is_empty()); // A failed transfer calls no hooks.
+
+        // Inside a transaction, the hooks only run once the outermost transaction commits, and
+        // never for rolled back changes.
+        balances.start_transaction();
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 10),
+            Ok(())
+        );
+        balances.rollback_transaction();
+        assert!(hooks::take().is_empty());
+        balances.start_transaction();
+        balances.start_transaction();
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 20),
+            Ok(())
+        );
+        balances.commit_transaction();
+        assert!(hooks::take().is_empty()); // An outer transaction can still undo the transfer.
+        balances.commit_transaction();
+        assert_eq!(
+            hooks::take(),
+            vec![
+                "first: alice -> bob: 20",
+                "second: alice -> bob: 20",
+                "first: alice = 50",
+                "first: bob = 50",
+            ]
+        );
+    }
+
+    #[test]
+    fn try_state_balances() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+
+        balances.set_balance(&"alice".to_string(), 100);
+        assert_eq!(
+            balances.transfer("alice".to_string(), "bob".to_string(), 100),
+            Ok(())
+        ); // Alice is emptied.
+        assert_eq!(balances.try_state(0), Ok(())); // Her zero balance was removed, not stored.
+
+        balances.insert_raw_balance(&"charlie".to_string(), 0); // Break the invariant.
+        assert_eq!(balances.try_state(0), Err("zero balance stored"));
+
+        balances.insert_raw_balance(&"charlie".to_string(), 5); // Created without issuance.
+        assert_eq!(
+            balances.try_state(0),
+            Err("total issuance does not match the sum of balances")
+        );
+    }
+
+    #[test]
+    fn transfer_policies() {
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances = super::Pallet::<TestConfig>::new_with_balances([
+            (alice.clone(), 100),
+            (bob.clone(), 100),
+        ])
+        .unwrap();
+
+        // Alice refuses to send to or receive from bob.
+        assert_eq!(
+            balances.set_transfer_policy(
+                alice.clone(),
+                TransferMode::DenyListed,
+                vec![bob.clone()]
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 10),
+            Err(BalancesError::SenderRestricted)
+        );
+        assert_eq!(
+            balances.transfer(bob.clone(), alice.clone(), 10),
+            Err(BalancesError::RecipientRestricted)
+        );
+        assert_eq!(
+            balances.transfer(alice.clone(), charlie.clone(), 10),
+            Ok(())
+        ); // Charlie is not denied.
+
+        // Charlie only accepts funds from alice.
+        assert_eq!(
+            balances.set_transfer_policy(
+                charlie.clone(),
+                TransferMode::AllowListedOnly,
+                vec![alice.clone()]
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            balances.transfer(bob.clone(), charlie.clone(), 10),
+            Err(BalancesError::RecipientRestricted)
+        );
+        assert_eq!(
+            balances.transfer(alice.clone(), charlie.clone(), 10),
+            Ok(())
+        );
+        assert_eq!(
+            balances.transfer(charlie.clone(), bob.clone(), 10),
+            Err(BalancesError::SenderRestricted)
+        ); // Bob is not listed either.
+        assert_eq!(balances.balance(&charlie), 20); // Only alice's transfers arrived.
+
+        // The list is bounded, and an over-long list leaves the old policy in place.
+        assert_eq!(
+            balances.set_transfer_policy(
+                charlie.clone(),
+                TransferMode::DenyListed,
+                vec![alice.clone(), bob.clone(), "dave".to_string()]
+            ),
+            Err(BalancesError::PolicyListTooLong)
+        );
+        assert_eq!(
+            balances.transfer_policy(&charlie).unwrap().mode,
+            TransferMode::AllowListedOnly
+        );
+
+        // Allowing everyone again removes the policy.
+        assert_eq!(
+            balances.set_transfer_policy(alice.clone(), TransferMode::AllowAll, vec![]),
+            Ok(())
+        );
+        assert_eq!(balances.transfer_policy(&alice), None);
+        assert_eq!(balances.transfer(alice, bob, 10), Ok(()));
+    }
+
+    #[test]
+    fn force_set_transfer_policy() {
+        let (root, treasury, bob, charlie) = (
+            "root".to_string(),
+            "treasury".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(treasury.clone(), 100)]).unwrap();
+
+        // Only root can impose a policy on another account.
+        let allow_charlie = |balances: &mut super::Pallet<TestConfig>, caller: &String| {
+            balances.force_set_transfer_policy(
+                caller.clone(),
+                bob.clone(),
+                TransferMode::AllowListedOnly,
+                vec![charlie.clone()],
+            )
+        };
+        assert_eq!(
+            allow_charlie(&mut balances, &root),
+            Err(BalancesError::NotRoot)
+        );
+        balances.set_root_account(Some(root.clone()));
+        assert_eq!(
+            allow_charlie(&mut balances, &bob),
+            Err(BalancesError::NotRoot)
+        );
+        assert_eq!(allow_charlie(&mut balances, &root), Ok(()));
+        assert_eq!(balances.transfer_policy(&bob).unwrap().list, vec![charlie]);
+
+        // A payout another pallet makes through the currency trait is blocked by the allow-list.
+        assert_eq!(
+            Currency::transfer(&mut balances, &treasury, &bob, 10),
+            Err("recipient restricted")
+        );
+        assert_eq!(balances.balance(&bob), 0);
+        assert_eq!(balances.balance(&treasury), 100);
+    }
+
+    #[test]
+    fn mint_and_burn() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        // The sum of all balances must always be the total issuance.
+        let sum = |balances: &super::Pallet<TestConfig>| {
+            balances.balance(&"alice".to_string()) + balances.balance(&"bob".to_string())
+        };
+
+        assert_eq!(balances.total_issuance(), 0);
+        // Only the root account can mint, to anyone.
+        assert_eq!(
+            balances.mint(bob.clone(), alice.clone(), 100),
+            Err(BalancesError::NotRoot)
+        );
+        let root = "root".to_string();
+        balances.set_root_account(Some(root.clone()));
+        assert_eq!(
+            balances.mint(bob.clone(), alice.clone(), 100),
+            Err(BalancesError::NotRoot)
+        );
+        assert_eq!(balances.total_issuance(), 0);
+        assert_eq!(balances.mint(root.clone(), alice.clone(), 100), Ok(()));
+        assert_eq!(balances.mint(root.clone(), bob.clone(), 50), Ok(()));
+        assert_eq!(balances.total_issuance(), 150);
+        assert_eq!(sum(&balances), 150);
+
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 30), Ok(())); // Transfers keep the issuance.
+        assert_eq!(balances.transfer(alice.clone(), alice.clone(), 70), Ok(())); // So do transfers to yourself.
+        assert_eq!(balances.total_issuance(), 150);
+        assert_eq!(sum(&balances), 150);
+
+        assert_eq!(balances.burn(alice.clone(), alice.clone(), 20), Ok(()));
+        assert_eq!(
+            balances.burn(alice.clone(), bob.clone(), 20),
+            Err(BalancesError::NotOwnFunds)
+        );
+        assert_eq!(
+            balances.burn(bob.clone(), bob.clone(), 81),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(balances.balance(&alice), 50);
+        assert_eq!(balances.total_issuance(), 130);
+        assert_eq!(sum(&balances), 130);
+
+        // Overflows fail without changing anything.
+        assert_eq!(
+            balances.mint(root.clone(), alice.clone(), u128::MAX),
+            Err(BalancesError::BalanceOverflow)
+        );
+        assert_eq!(
+            balances.mint(root, "charlie".to_string(), u128::MAX),
+            Err(BalancesError::IssuanceOverflow)
+        );
+        assert_eq!(balances.total_issuance(), 130);
+
+        // `set_balance` keeps the issuance in sync too.
+        balances.set_balance(&bob, 10);
+        assert_eq!(balances.total_issuance(), 60);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    // A config with an existential deposit of 10, where the dust of reaped accounts is burned.
+    type DepositConfig = BaseConfig<10>;
+
+    // A config like `DepositConfig`, where the dust of reaped accounts goes to a treasury.
+    type DustConfig = BaseConfig<10, Treasury>;
+
+    struct Treasury;
+
+    impl Get<Option<String>> for Treasury {
+        fn get() -> Option<String> {
+            Some("treasury".to_string())
+        }
+    }
+
+    #[test]
+    fn existential_deposit() {
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances =
+            super::Pallet::<DepositConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+        assert!(balances.account_exists(&alice));
+        assert!(!balances.account_exists(&bob));
+
+        // Creating an account needs at least the existential deposit.
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 9),
+            Err(BalancesError::BelowExistentialDeposit)
+        );
+        assert_eq!(
+            balances.deposit(&bob, 9),
+            Err(BalancesError::BelowExistentialDeposit)
+        );
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 10), Ok(()));
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 1), Ok(())); // Existing accounts can receive less.
+
+        // Alice keeps 5 after sending 84, so she is reaped and her dust is burned.
+        assert_eq!(
+            balances.transfer(alice.clone(), charlie.clone(), 84),
+            Ok(())
+        );
+        assert!(!balances.account_exists(&alice));
+        assert_eq!(balances.balance(&alice), 0);
+        assert_eq!(balances.total_issuance(), 95);
+        assert!(balances.take_events().contains(&Event::DustLost {
+            who: alice.clone(),
+            amount: 5
+        }));
+
+        // Burning down to dust reaps too.
+        assert_eq!(balances.burn(bob.clone(), bob.clone(), 2), Ok(()));
+        assert!(!balances.account_exists(&bob));
+        assert_eq!(balances.total_issuance(), 84);
+        assert_eq!(balances.try_state(0), Ok(()));
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                Event::DustLost {
+                    who: bob.clone(),
+                    amount: 9
+                },
+                Event::Burned {
+                    who: bob,
+                    amount: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reserve_and_unreserve() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let mut balances =
+            super::Pallet::<DepositConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+
+        assert_eq!(
+            balances.reserve(&alice, 101),
+            Err(BalancesError::InsufficientFreeFunds)
+        );
+        assert_eq!(balances.reserve(&alice, 60), Ok(()));
+        assert_eq!(balances.balance(&alice), 40);
+        assert_eq!(balances.reserved_balance(&alice), 60);
+        assert_eq!(balances.total_issuance(), 100); // Reserved funds still exist.
+
+        // The transfer would succeed with all 100 free, but only 40 can be spent.
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 50),
+            Err(BalancesError::InsufficientFunds)
+        );
+
+        // Unreserving part of the funds makes the transfer possible again.
+        assert_eq!(balances.unreserve(&alice, 10), 10);
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 50), Ok(()));
+        assert_eq!(balances.reserved_balance(&alice), 50);
+
+        // Spending all free funds does not reap an account with reserved funds.
+        assert_eq!(balances.balance(&alice), 0);
+        assert!(balances.account_exists(&alice));
+
+        // Reserved funds can be paid out to another account, even from a frozen one.
+        balances.frozen.insert(alice.clone());
+        assert_eq!(
+            balances.repatriate_reserved(&alice, &bob, 51),
+            Err(BalancesError::InsufficientReservedFunds)
+        );
+        assert_eq!(balances.repatriate_reserved(&alice, &bob, 20), Ok(()));
+        assert_eq!(balances.balance(&bob), 70);
+        assert_eq!(balances.reserved_balance(&alice), 30);
+        assert_eq!(balances.total_issuance(), 100);
+
+        // At most the reserved balance is unreserved.
+        assert_eq!(balances.unreserve(&alice, 80), 30);
+        assert_eq!(balances.balance(&alice), 30);
+        assert_eq!(balances.reserved_balance(&alice), 0);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn repatriate_reserved() {
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances = super::Pallet::<DepositConfig>::new_with_balances([
+            (alice.clone(), 15),
+            (bob.clone(), 20),
+        ])
+        .unwrap();
+        assert_eq!(balances.reserve(&alice, 10), Ok(()));
+
+        // The transfer policies of both sides apply to reserved funds too.
+        assert_eq!(
+            balances.set_transfer_policy(bob.clone(), TransferMode::AllowListedOnly, vec![charlie]),
+            Ok(())
+        );
+        assert_eq!(
+            balances.repatriate_reserved(&alice, &bob, 10),
+            Err(BalancesError::RecipientRestricted)
+        );
+        assert_eq!(balances.reserved_balance(&alice), 10);
+
+        // Once nothing is reserved, the dust left in the free balance of alice is reaped.
+        assert_eq!(
+            balances.set_transfer_policy(bob.clone(), TransferMode::AllowAll, vec![]),
+            Ok(())
+        );
+        balances.take_events();
+        assert_eq!(balances.repatriate_reserved(&alice, &bob, 10), Ok(()));
+        assert!(!balances.account_exists(&alice));
+        assert_eq!(balances.balance(&bob), 30);
+        assert_eq!(balances.total_issuance(), 30);
+        assert!(balances.take_events().contains(&Event::DustLost {
+            who: alice,
+            amount: 5
+        }));
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn named_locks() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        const STAKING: LockIdentifier = *b"staking ";
+        const VOTING: LockIdentifier = *b"voting  ";
+        balances.set_balance(&alice, 100);
+        balances.set_balance(&bob, 100);
+
+        // Two locks on the same account, the larger one wins.
+        balances.set_lock(STAKING, &alice, 30);
+        balances.set_lock(VOTING, &alice, 60);
+        assert_eq!(balances.locked_balance(&alice), 60);
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 41),
+            Err(BalancesError::FundsLocked)
+        );
+        assert_eq!(
+            balances.burn(alice.clone(), alice.clone(), 41),
+            Err(BalancesError::FundsLocked)
+        );
+        assert_eq!(
+            balances.reserve(&alice, 41),
+            Err(BalancesError::FundsLocked)
+        );
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 40), Ok(()));
+
+        // Setting an existing lock overwrites it instead of stacking.
+        balances.set_lock(STAKING, &alice, 10);
+        assert_eq!(balances.locked_balance(&alice), 60);
+
+        // Removing one lock while the other still restricts transfers.
+        balances.remove_lock(VOTING, &alice);
+        assert_eq!(balances.locked_balance(&alice), 10);
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 51),
+            Err(BalancesError::FundsLocked)
+        );
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 50), Ok(()));
+
+        // Locks do not affect incoming transfers.
+        assert_eq!(balances.transfer(bob.clone(), alice.clone(), 90), Ok(()));
+        assert_eq!(balances.balance(&alice), 100);
+
+        balances.remove_lock(STAKING, &alice);
+        assert_eq!(balances.locked_balance(&alice), 0);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    // Locks and reserves keep an account holding dust alive, until the last one is gone.
+    #[test]
+    fn reap_after_last_lock_or_reserve() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        const STAKING: LockIdentifier = *b"staking ";
+        let mut balances = super::Pallet::<DepositConfig>::new_with_balances([
+            (alice.clone(), 100),
+            (bob.clone(), 15),
+        ])
+        .unwrap();
+
+        // Slashing takes alice below the existential deposit, but her lock keeps her alive.
+        balances.set_lock(STAKING, &alice, 50);
+        assert_eq!(balances.slash(&alice, 95), 95);
+        assert!(balances.account_exists(&alice));
+        balances.take_events();
+        balances.remove_lock(STAKING, &alice);
+        assert!(!balances.account_exists(&alice));
+        assert_eq!(balances.total_issuance(), 15);
+        assert_eq!(
+            balances.take_events(),
+            vec![Event::DustLost {
+                who: alice,
+                amount: 5
+            }]
+        );
+        assert_eq!(balances.try_state(0), Ok(()));
+
+        // The same goes for a reserve, once all of it is unreserved.
+        assert_eq!(balances.reserve(&bob, 3), Ok(()));
+        assert_eq!(balances.slash(&bob, 10), 10);
+        assert_eq!(balances.unreserve(&bob, 2), 2);
+        assert!(balances.account_exists(&bob));
+        assert_eq!(balances.unreserve(&bob, 1), 1);
+        assert!(!balances.account_exists(&bob));
+        assert_eq!(balances.total_issuance(), 0);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn transfer_all() {
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances =
+            super::Pallet::<DepositConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+
+        assert_eq!(balances.transfer_all(alice.clone(), bob.clone()), Ok(()));
+        assert_eq!(balances.balance(&bob), 100);
+        assert!(!balances.account_exists(&alice)); // Alice's entry is removed.
+
+        // An empty account can call it, nothing happens.
+        assert_eq!(balances.transfer_all(alice.clone(), bob.clone()), Ok(()));
+        assert_eq!(balances.balance(&bob), 100);
+
+        // Locked funds stay behind.
+        balances.set_lock(*b"staking ", &bob, 30);
+        assert_eq!(balances.transfer_all(bob.clone(), charlie.clone()), Ok(()));
+        assert_eq!(balances.balance(&bob), 30);
+        assert_eq!(balances.balance(&charlie), 70);
+        assert_eq!(balances.total_issuance(), 100);
+    }
+
+    #[test]
+    fn force_transfer() {
+        let (root, alice, bob) = ("root".to_string(), "alice".to_string(), "bob".to_string());
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+
+        // Without a root account, nobody can force a transfer.
+        assert_eq!(
+            balances.force_transfer(root.clone(), alice.clone(), bob.clone(), 10),
+            Err(BalancesError::NotRoot)
+        );
+
+        balances.set_root_account(Some(root.clone()));
+        assert_eq!(balances.root_account(), Some(&root));
+        assert_eq!(
+            balances.force_transfer(root.clone(), alice.clone(), bob.clone(), 10),
+            Ok(())
+        );
+        assert_eq!(balances.balance(&alice), 90);
+        assert_eq!(balances.balance(&bob), 10);
+
+        // Other accounts, including the owner of the funds, are not root.
+        assert_eq!(
+            balances.force_transfer(alice.clone(), alice.clone(), bob.clone(), 10),
+            Err(BalancesError::NotRoot)
+        );
+        assert_eq!(
+            balances.force_transfer(bob.clone(), alice.clone(), bob.clone(), 10),
+            Err(BalancesError::NotRoot)
+        );
+
+        // The same checked arithmetic as a transfer applies.
+        assert_eq!(
+            balances.force_transfer(root.clone(), alice.clone(), bob.clone(), 91),
+            Err(BalancesError::InsufficientFunds)
+        );
+        balances.insert_raw_balance(&bob, u128::MAX);
+        assert_eq!(
+            balances.force_transfer(root.clone(), alice.clone(), bob.clone(), 10),
+            Err(BalancesError::Overflow)
+        );
+        assert_eq!(balances.balance(&alice), 90); // Nothing moved.
+        balances.insert_raw_balance(&bob, 10);
+
+        // Root recovers funds no transfer of alice could send: she is frozen, only sends to
+        // charlie, has spent her limit and the amount is below the minimum transfer.
+        let charlie = "charlie".to_string();
+        assert_eq!(balances.freeze(root.clone(), alice.clone()), Ok(()));
+        assert_eq!(
+            balances.set_transfer_policy(
+                alice.clone(),
+                TransferMode::AllowListedOnly,
+                vec![charlie]
+            ),
+            Ok(())
+        );
+        assert_eq!(balances.set_spending_limit(alice.clone(), 0), Ok(()));
+        assert_eq!(balances.set_minimum_transfer(root.clone(), 50), Ok(()));
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 20),
+            Err(BalancesError::FrozenAccount)
+        );
+        assert_eq!(
+            balances.force_transfer(root, alice.clone(), bob.clone(), 20),
+            Ok(())
+        );
+        assert_eq!(balances.balance(&alice), 70);
+        assert_eq!(balances.balance(&bob), 30);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn allowances() {
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 10),
+            Err(BalancesError::NoAllowance)
+        );
+        assert_eq!(balances.approve(alice.clone(), bob.clone(), 50), Ok(()));
+        assert_eq!(balances.allowance(&alice, &bob), 50);
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 51),
+            Err(BalancesError::AllowanceTooLow)
+        );
+
+        // Spending part of the allowance, then exactly what is left.
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 20),
+            Ok(())
+        );
+        assert_eq!(balances.allowance(&alice, &bob), 30);
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), bob.clone(), 30),
+            Ok(())
+        );
+        assert_eq!(balances.allowance(&alice, &bob), 0);
+        assert_eq!(balances.balance(&alice), 50);
+        assert_eq!(balances.balance(&bob), 30);
+        assert_eq!(balances.balance(&charlie), 20);
+
+        // Re-approving replaces the allowance, even with a lower value.
+        assert_eq!(balances.approve(alice.clone(), bob.clone(), 80), Ok(()));
+        assert_eq!(balances.approve(alice.clone(), bob.clone(), 60), Ok(()));
+        assert_eq!(balances.allowance(&alice, &bob), 60);
+
+        // A sufficient allowance does not help when the owner lacks funds, and nothing is used up.
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 55),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(balances.allowance(&alice, &bob), 60);
+
+        // Sending the owner's funds back to the owner moves nothing, and is rejected.
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), alice.clone(), 10),
+            Err(BalancesError::SelfTransfer)
+        );
+        assert_eq!(balances.allowance(&alice, &bob), 60);
+        assert_eq!(balances.balance(&alice), 50);
+
+        // Approving zero removes the allowance.
+        assert_eq!(balances.approve(alice.clone(), bob.clone(), 0), Ok(()));
+        assert_eq!(
+            balances.transfer_from(bob, alice, charlie, 1),
+            Err(BalancesError::NoAllowance)
+        );
+    }
+
+    #[test]
+    fn burn_from() {
+        let (alice, bob, charlie, root) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+            "root".to_string(),
+        );
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+        balances.set_root_account(Some(root.clone()));
+
+        assert_eq!(
+            balances.burn_from(bob.clone(), alice.clone(), 10),
+            Err(BalancesError::NoAllowance)
+        );
+        assert_eq!(balances.approve(alice.clone(), bob.clone(), 150), Ok(()));
+        assert_eq!(
+            balances.burn_from(bob.clone(), alice.clone(), 151),
+            Err(BalancesError::AllowanceTooLow)
+        );
+        // The allowance is large enough, but Alice is not, and nothing is used up.
+        assert_eq!(
+            balances.burn_from(bob.clone(), alice.clone(), 120),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(balances.allowance(&alice, &bob), 150);
+
+        assert_eq!(balances.burn_from(bob.clone(), alice.clone(), 30), Ok(()));
+        assert_eq!(balances.balance(&alice), 70);
+        assert_eq!(balances.total_issuance(), 70);
+        assert_eq!(balances.allowance(&alice, &bob), 120);
+
+        // Transfers and burns spend the same allowance.
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 50),
+            Ok(())
+        );
+        assert_eq!(balances.allowance(&alice, &bob), 70);
+        assert_eq!(balances.burn_from(bob.clone(), alice.clone(), 15), Ok(()));
+        assert_eq!(balances.allowance(&alice, &bob), 55);
+        assert_eq!(balances.balance(&alice), 5);
+        assert_eq!(balances.total_issuance(), 55);
+
+        // The root account needs no allowance, and uses up none.
+        assert_eq!(balances.burn_from(root, charlie.clone(), 50), Ok(()));
+        assert_eq!(balances.balance(&charlie), 0);
+        assert_eq!(balances.total_issuance(), 5);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn transfer_batch() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+
+        // A batch can drain exactly the caller's balance.
+        balances.set_balance(&alice, 100);
+        let batch = vec![(bob.clone(), 30), (charlie.clone(), 50), (bob.clone(), 20)];
+        assert_eq!(balances.transfer_batch(alice.clone(), batch), Ok(()));
+        assert_eq!(balances.balance(&alice), 0);
+        assert_eq!(balances.balance(&bob), 50);
+        assert_eq!(balances.balance(&charlie), 50);
+
+        // The third item needs more than is left after the first two, so none of them happen.
+        let batch = vec![
+            (alice.clone(), 10),
+            (charlie.clone(), 30),
+            (alice.clone(), 11),
+        ];
+        assert_eq!(
+            balances.transfer_batch(bob.clone(), batch),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(balances.balance(&alice), 0);
+        assert_eq!(balances.balance(&bob), 50);
+        assert_eq!(balances.balance(&charlie), 50);
+        assert_eq!(balances.total_issuance(), 100);
+
+        // An empty batch does nothing.
+        assert_eq!(balances.transfer_batch(bob.clone(), vec![]), Ok(()));
+        assert_eq!(balances.balance(&bob), 50);
+    }
+
+    #[test]
+    fn events() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+
+        balances.set_balance(&alice, 100);
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 200),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(
+            balances.transfer_batch(alice.clone(), vec![(bob.clone(), 10), (bob.clone(), 20)]),
+            Ok(())
+        );
+        balances.set_root_account(Some(alice.clone()));
+        assert_eq!(balances.mint(alice.clone(), bob.clone(), 5), Ok(()));
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                Event::BalanceSet {
+                    who: alice.clone(),
+                    amount: 100
+                },
+                Event::Transfer {
+                    from: alice.clone(),
+                    to: bob.clone(),
+                    amount: 10
+                },
+                Event::Transfer {
+                    from: alice,
+                    to: bob.clone(),
+                    amount: 20
+                },
+                Event::Minted {
+                    who: bob,
+                    amount: 5
+                },
+            ]
+        );
+        assert_eq!(balances.take_events(), vec![]); // Taking the events empties the buffer.
+    }
+
+    #[test]
+    fn new_with_balances() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let balances = super::Pallet::<DepositConfig>::new_with_balances([
+            (alice.clone(), 100),
+            (bob.clone(), 50),
+        ])
+        .unwrap();
+        assert_eq!(balances.balance(&alice), 100);
+        assert_eq!(balances.total_issuance(), 150);
+
+        assert_eq!(
+            super::Pallet::<DepositConfig>::new_with_balances([(alice.clone(), 100), (alice, 50)])
+                .err(),
+            Some("duplicate account in genesis")
+        );
+        assert_eq!(
+            super::Pallet::<DepositConfig>::new_with_balances([(bob, 5)]).err(),
+            Some("amount below existential deposit")
+        );
+    }
+
+    #[test]
+    fn genesis_config() {
+        let mut balances = super::Pallet::<DepositConfig>::new();
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+
+        let genesis = super::GenesisConfig::<DepositConfig> {
+            balances: vec![(alice.clone(), 100), (bob.clone(), 50)],
+            minimum_transfer: 0,
+        };
+        assert_eq!(genesis.build(&mut balances), Ok(()));
+        assert_eq!(balances.balance(&alice), 100);
+        assert_eq!(balances.balance(&bob), 50);
+        assert_eq!(balances.total_issuance(), 150);
+        assert_eq!(balances.try_state(0), Ok(()));
+
+        // Invalid genesis lists change nothing.
+        let mut balances = super::Pallet::<DepositConfig>::new();
+        let duplicate = super::GenesisConfig::<DepositConfig> {
+            balances: vec![(alice.clone(), 100), (bob.clone(), 50), (alice.clone(), 10)],
+            minimum_transfer: 0,
+        };
+        assert_eq!(
+            duplicate.build(&mut balances),
+            Err(BalancesError::DuplicateGenesisAccount)
+        );
+        let dust = super::GenesisConfig::<DepositConfig> {
+            balances: vec![(alice.clone(), 100), (bob.clone(), 5)],
+            minimum_transfer: 0,
+        };
+        assert_eq!(
+            dust.build(&mut balances),
+            Err(BalancesError::BelowExistentialDeposit)
+        );
+        assert_eq!(balances.balance(&alice), 0);
+        assert_eq!(balances.total_issuance(), 0);
+
+        // The genesis config can not overwrite existing balances.
+        balances.set_balance(&alice, 30);
+        assert_eq!(
+            genesis.build(&mut balances),
+            Err(BalancesError::GenesisNotEmpty)
+        );
+        assert_eq!(balances.balance(&alice), 30);
+        assert_eq!(balances.balance(&bob), 0);
+        assert_eq!(balances.total_issuance(), 30);
+    }
+
+    #[test]
+    fn assets() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        const GOLD: u32 = 1;
+        const SILVER: u32 = 2;
+
+        assert_eq!(
+            balances.transfer_asset(alice.clone(), GOLD, bob.clone(), 0),
+            Err(BalancesError::UnknownAsset)
+        );
+        assert_eq!(
+            balances.mint_asset(alice.clone(), GOLD, alice.clone(), 10),
+            Err(BalancesError::UnknownAsset)
+        );
+        assert_eq!(balances.create_asset(alice.clone(), GOLD), Ok(()));
+        assert_eq!(
+            balances.create_asset(bob.clone(), GOLD),
+            Err(BalancesError::AssetAlreadyExists)
+        );
+        assert_eq!(balances.create_asset(bob.clone(), SILVER), Ok(()));
+
+        // Only the creator can mint.
+        assert_eq!(
+            balances.mint_asset(bob.clone(), GOLD, bob.clone(), 10),
+            Err(BalancesError::NotAssetCreator)
+        );
+        assert_eq!(
+            balances.mint_asset(alice.clone(), GOLD, alice.clone(), 100),
+            Ok(())
+        );
+        assert_eq!(
+            balances.mint_asset(bob.clone(), SILVER, bob.clone(), 7),
+            Ok(())
+        );
+
+        assert_eq!(
+            balances.transfer_asset(alice.clone(), GOLD, bob.clone(), 101),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(
+            balances.transfer_asset(alice.clone(), GOLD, bob.clone(), 40),
+            Ok(())
+        );
+        assert_eq!(balances.balance_of(GOLD, &alice), 60);
+        assert_eq!(balances.balance_of(GOLD, &bob), 40);
+        assert_eq!(balances.balance_of(SILVER, &bob), 7);
+        assert_eq!(
+            balances.asset(GOLD).map(|details| details.total_issuance),
+            Some(100)
+        );
+        assert_eq!(
+            balances.asset(SILVER).map(|details| details.total_issuance),
+            Some(7)
+        );
+
+        // Assets are separate from the native token.
+        assert_eq!(balances.balance(&alice), 0);
+        assert_eq!(balances.total_issuance(), 0);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn withdraw_fee() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let mut balances = super::Pallet::<DepositConfig>::new_with_balances([
+            (alice.clone(), 100),
+            (bob.clone(), 15),
+        ])
+        .unwrap();
+
+        assert_eq!(balances.withdraw_fee(&alice, 3), Ok(()));
+        assert_eq!(balances.balance(&alice), 97);
+        assert_eq!(
+            balances.withdraw_fee(&alice, 98),
+            Err(BalancesError::CannotPayFee)
+        );
+
+        // Paying the fee can reap the account, and its dust is burned.
+        assert_eq!(balances.withdraw_fee(&bob, 6), Ok(()));
+        assert_eq!(balances.balance(&bob), 0);
+        assert_eq!(balances.collected_fees(), 9);
+        assert_eq!(balances.total_issuance(), 106);
+        assert_eq!(balances.try_state(0), Ok(()));
+        assert_eq!(
+            balances.take_events().last(),
+            Some(&Event::Withdrawn {
+                who: bob,
+                amount: 6,
+                reason: WithdrawReason::Fee
+            })
+        );
+    }
+
+    #[test]
+    fn freeze_and_thaw() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (root, alice, bob) = ("root".to_string(), "alice".to_string(), "bob".to_string());
+        balances.set_root_account(Some(root.clone()));
+        balances.set_balance(&alice, 100);
+        balances.set_balance(&bob, 100);
+
+        // Only the root account can freeze and thaw.
+        assert_eq!(
+            balances.freeze(bob.clone(), alice.clone()),
+            Err(BalancesError::NotRoot)
+        );
+        assert_eq!(balances.freeze(root.clone(), alice.clone()), Ok(()));
+        assert!(balances.is_frozen(&alice));
+        assert_eq!(
+            balances.thaw(bob.clone(), alice.clone()),
+            Err(BalancesError::NotRoot)
+        );
+
+        // A frozen account can not send, but can still receive.
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 10),
+            Err(BalancesError::FrozenAccount)
+        );
+        assert_eq!(
+            balances.transfer_all(alice.clone(), bob.clone()),
+            Err(BalancesError::FrozenAccount)
+        );
+        assert_eq!(balances.transfer(bob.clone(), alice.clone(), 10), Ok(()));
+        assert_eq!(balances.balance(&alice), 110);
+
+        assert_eq!(balances.thaw(root, alice.clone()), Ok(()));
+        assert!(!balances.is_frozen(&alice));
+        assert_eq!(balances.transfer(alice.clone(), bob, 10), Ok(()));
+        assert_eq!(balances.balance(&alice), 100);
+    }
+
+    #[test]
+    fn accounts() {
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(charlie.clone(), 100)]).unwrap();
+        assert_eq!(
+            balances.transfer(charlie.clone(), alice.clone(), 30),
+            Ok(())
+        );
+        assert_eq!(balances.transfer(charlie.clone(), bob.clone(), 70), Ok(()));
+
+        // Charlie was emptied, and the others are listed in sorted order.
+        assert_eq!(
+            balances.accounts().collect::<Vec<_>>(),
+            vec![(&alice, &30), (&bob, &70)]
+        );
+        assert_eq!(balances.account_count(), 2);
+        assert_eq!(
+            balances.total_of(&[alice.clone(), bob.clone(), charlie]),
+            Ok(100)
+        );
+        assert_eq!(
+            balances
+                .accounts()
+                .map(|(_, balance)| balance)
+                .sum::<u128>(),
+            balances.total_issuance()
+        );
+
+        balances.set_balance(&bob, u128::MAX - 30);
+        assert_eq!(
+            balances.total_of(&[alice.clone(), bob.clone()]),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            balances.total_of(&[alice.clone(), bob, alice]),
+            Err(BalancesError::Overflow)
+        );
+    }
+
+    #[test]
+    fn slash() {
+        let mut balances = super::Pallet::<DepositConfig>::new();
+        let (root, alice, bob) = ("root".to_string(), "alice".to_string(), "bob".to_string());
+        balances.set_root_account(Some(root.clone()));
+        balances.set_balance(&alice, 100);
+        balances.set_balance(&bob, 50);
+
+        assert_eq!(balances.slash(&alice, 30), 30);
+        assert_eq!(balances.balance(&alice), 70);
+        assert_eq!(balances.slash(&bob, 80), 50); // Only what the account has is slashed.
+        assert_eq!(balances.balance(&bob), 0);
+        assert_eq!(balances.slash(&"nobody".to_string(), 10), 0);
+        assert_eq!(balances.total_issuance(), 70);
+
+        // Slashing below the existential deposit reaps the account.
+        assert_eq!(balances.slash(&alice, 65), 65);
+        assert_eq!(balances.balance(&alice), 0);
+        assert_eq!(balances.total_issuance(), 0);
+        assert_eq!(balances.try_state(0), Ok(()));
+
+        // Only the root account can slash through a call.
+        balances.set_balance(&alice, 100);
+        assert_eq!(
+            balances.slash_account(bob, alice.clone(), 10),
+            Err(BalancesError::NotRoot)
+        );
+        assert_eq!(balances.slash_account(root, alice.clone(), 10), Ok(()));
+        assert_eq!(balances.balance(&alice), 90);
+        assert_eq!(balances.total_issuance(), 90);
+    }
+
+    #[test]
+    fn transfer_with_memo() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        balances.set_balance(&alice, 100);
+        balances.take_events();
+
+        assert_eq!(
+            balances.transfer_with_memo(alice.clone(), bob.clone(), 10, b"rent!".to_vec()),
+            Err(BalancesError::MemoTooLong)
+        );
+        assert_eq!(balances.balance(&alice), 100);
+        assert_eq!(
+            balances.transfer_with_memo(alice.clone(), bob.clone(), 10, b"rent".to_vec()),
+            Ok(())
+        );
+        assert_eq!(balances.balance(&bob), 10);
+        assert_eq!(
+            balances.take_events(),
+            vec![Event::TransferWithMemo {
+                from: alice,
+                to: bob,
+                amount: 10,
+                memo: b"rent".to_vec()
+            }]
+        );
+    }
+
+    #[test]
+    fn vesting() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+        let schedule = VestingSchedule {
+            locked: 50,
+            per_block: 10,
+            starting_block: 2,
+        };
+
+        assert_eq!(
+            balances.vest(bob.clone()),
+            Err(BalancesError::NoVestingSchedule)
+        );
+        let never = VestingSchedule {
+            per_block: 0,
+            ..schedule.clone()
+        };
+        assert_eq!(
+            balances.vested_transfer(alice.clone(), bob.clone(), never),
+            Err(BalancesError::VestingNeverUnlocks)
+        );
+        assert_eq!(
+            balances.vested_transfer(alice.clone(), bob.clone(), schedule.clone()),
+            Ok(())
+        );
+        assert_eq!(
+            balances.vested_transfer(alice.clone(), bob.clone(), schedule),
+            Err(BalancesError::VestingScheduleExists)
+        );
+        assert_eq!(balances.balance(&bob), 50);
+        assert_eq!(balances.vesting_balance(&bob), 50);
+        assert_eq!(
+            balances.transfer(bob.clone(), alice.clone(), 1),
+            Err(BalancesError::FundsLocked)
+        );
+
+        // The unlocked amount grows by 10 every block from block 2 on, and caps at the total.
+        let mut unlocked = vec![];
+        for block_number in 1..=8 {
+            balances.on_block_start(block_number, None);
+            unlocked.push(50 - balances.vesting_balance(&bob));
+        }
+        assert_eq!(unlocked, vec![0, 0, 10, 20, 30, 40, 50, 50]);
+
+        // The lock only shrinks once the vested funds are claimed.
+        balances.on_block_start(4, None);
+        assert_eq!(
+            balances.transfer(bob.clone(), alice.clone(), 20),
+            Err(BalancesError::FundsLocked)
+        );
+        assert_eq!(balances.vest(bob.clone()), Ok(()));
+        assert_eq!(balances.locked_balance(&bob), 30);
+        assert_eq!(
+            balances.transfer(bob.clone(), alice.clone(), 21),
+            Err(BalancesError::FundsLocked)
+        );
+        assert_eq!(balances.transfer(bob.clone(), alice.clone(), 20), Ok(()));
+
+        // Once everything vested, the schedule and its lock are gone.
+        balances.on_block_start(7, None);
+        assert_eq!(balances.vest(bob.clone()), Ok(()));
+        assert_eq!(balances.vesting(&bob), None);
+        assert_eq!(balances.locked_balance(&bob), 0);
+        assert_eq!(balances.transfer(bob, alice, 30), Ok(()));
+    }
+
+    #[test]
+    fn force_set_balance() {
+        let mut balances = super::Pallet::<DepositConfig>::new();
+        let (root, alice) = ("root".to_string(), "alice".to_string());
+        balances.set_root_account(Some(root.clone()));
+
+        assert_eq!(
+            balances.force_set_balance(alice.clone(), alice.clone(), 100),
+            Err(BalancesError::NotRoot)
+        );
+        assert_eq!(
+            balances.force_set_balance(root.clone(), alice.clone(), 100),
+            Ok(())
+        );
+        assert_eq!(
+            balances.force_set_balance(root.clone(), alice.clone(), 5),
+            Err(BalancesError::BelowExistentialDeposit)
+        );
+        assert_eq!(
+            balances.force_set_balance(root.clone(), alice.clone(), 40),
+            Ok(())
+        );
+        assert_eq!(balances.balance(&alice), 40);
+        assert_eq!(balances.total_issuance(), 40);
+        assert_eq!(
+            balances.force_set_balance(root.clone(), root.clone(), u128::MAX),
+            Err(BalancesError::IssuanceOverflow)
+        );
+
+        assert_eq!(balances.force_set_balance(root, alice.clone(), 0), Ok(())); // Removes the account.
+        assert_eq!(balances.balance(&alice), 0);
+        assert_eq!(balances.total_issuance(), 0);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn minimum_transfer() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (root, alice, bob) = ("root".to_string(), "alice".to_string(), "bob".to_string());
+        balances.set_root_account(Some(root.clone()));
+        balances.set_balance(&alice, 100);
+
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 0),
+            Err(BalancesError::ZeroTransfer)
+        );
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 1), Ok(()));
+
+        assert_eq!(
+            balances.set_minimum_transfer(alice.clone(), 5),
+            Err(BalancesError::NotRoot)
+        );
+        assert_eq!(balances.set_minimum_transfer(root.clone(), 5), Ok(()));
+        assert_eq!(balances.minimum_transfer(), 5);
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 4),
+            Err(BalancesError::BelowMinimum)
+        );
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 5), Ok(()));
+        assert_eq!(balances.balance(&bob), 6);
+
+        // A minimum of zero still rejects transfers of zero.
+        assert_eq!(balances.set_minimum_transfer(root, 0), Ok(()));
+        assert_eq!(
+            balances.transfer(alice, bob, 0),
+            Err(BalancesError::ZeroTransfer)
+        );
+    }
+
+    #[test]
+    fn transfer_shares() {
+        let (alice, bob, charlie) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+        );
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 1000)]).unwrap();
+
+        assert_eq!(
+            balances.transfer_shares(alice.clone(), 100, vec![]),
+            Err(BalancesError::NoRecipients)
+        );
+        assert_eq!(
+            balances.transfer_shares(alice.clone(), 100, vec![(bob.clone(), 0)]),
+            Err(BalancesError::ZeroShare)
+        );
+        assert_eq!(
+            balances.transfer_shares(alice.clone(), 1001, vec![(bob.clone(), 1)]),
+            Err(BalancesError::InsufficientFunds)
+        );
+
+        // 100 split 3 ways is 33 each, and the first recipient gets the remaining 1.
+        let shares = vec![(bob.clone(), 1), (charlie.clone(), 1), (alice.clone(), 1)];
+        assert_eq!(balances.transfer_shares(alice.clone(), 100, shares), Ok(()));
+        assert_eq!(balances.balance(&bob), 34);
+        assert_eq!(balances.balance(&charlie), 33);
+        assert_eq!(balances.balance(&alice), 933);
+
+        // Uneven weights: 10 split 2:1 is 6 and 3, plus the remaining 1 for the first recipient.
+        let shares = vec![(charlie.clone(), 2), (bob.clone(), 1)];
+        assert_eq!(balances.transfer_shares(alice.clone(), 10, shares), Ok(()));
+        assert_eq!(balances.balance(&charlie), 40);
+        assert_eq!(balances.balance(&bob), 37);
+        assert_eq!(balances.balance(&alice), 923);
+
+        // A failing transfer rolls back the whole split.
+        assert_eq!(
+            balances.set_transfer_policy(
+                bob.clone(),
+                TransferMode::DenyListed,
+                vec![alice.clone()]
+            ),
+            Ok(())
+        );
+        let shares = vec![(charlie.clone(), 1), (bob.clone(), 1)];
+        assert_eq!(
+            balances.transfer_shares(alice.clone(), 100, shares),
+            Err(BalancesError::RecipientRestricted)
+        );
+        assert_eq!(balances.balance(&charlie), 40);
+        assert_eq!(balances.balance(&alice), 923);
+
+        // Totals too large to multiply by a share are split too.
+        let total = u128::MAX - 1;
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), total)]).unwrap();
+        let shares = vec![(bob.clone(), 3), (charlie.clone(), 2)];
+        assert_eq!(
+            balances.transfer_shares(alice.clone(), total, shares),
+            Ok(())
+        );
+        assert_eq!(balances.balance(&bob), total / 5 * 3 + 3);
+        assert_eq!(balances.balance(&charlie), total / 5 * 2 + 1);
+        assert_eq!(balances.balance(&alice), 0);
+    }
+
+    #[test]
+    fn currency() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+
+        assert_eq!(Currency::transfer(&mut balances, &alice, &bob, 30), Ok(()));
+        assert_eq!(Currency::withdraw(&mut balances, &alice, 20), Ok(()));
+        assert_eq!(
+            Currency::withdraw(&mut balances, &alice, 51),
+            Err(BalancesError::InsufficientFunds.into()) // The currency reports the runtime-level error.
+        );
+        assert_eq!(Currency::deposit(&mut balances, &bob, 5), Ok(()));
+        assert_eq!(balances.free_balance(&alice), 50);
+        assert_eq!(balances.free_balance(&bob), 35);
+        assert_eq!(balances.total_issuance(), 85);
+        assert!(balances.take_events().contains(&Event::Withdrawn {
+            who: alice,
+            amount: 20,
+            reason: WithdrawReason::Fee
+        }));
+    }
+
+    #[test]
+    fn error_messages() {
+        // The runtime sees the same message as the one displayed.
+        let error = BalancesError::InsufficientFunds;
+        assert_eq!(error.to_string(), "Not enough funds.");
+        assert_eq!(<&'static str>::from(error), "Not enough funds.");
+        assert_eq!(BalancesError::NotRoot.to_string(), "caller is not root");
+    }
+
+    // Times 10k transfers between 100k accounts. Only built with the `bench` feature.
+    #[cfg(feature = "bench")]
+    #[test]
+    fn bench_transfers() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let accounts = (0..100_000)
+            .map(|i| format!("account {:06}", i))
+            .collect::<Vec<_>>();
+        for who in &accounts {
+            balances.set_balance(who, 1_000);
+        }
+
+        let start = std::time::Instant::now();
+        for i in 0..10_000 {
+            let from = accounts[i * 7 % accounts.len()].clone();
+            let to = accounts[i * 13 % accounts.len()].clone();
+            assert_eq!(balances.transfer(from, to, 1), Ok(()));
+        }
+        println!("10k transfers between 100k accounts: {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn richest() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        assert_eq!(balances.richest(3), vec![]);
+        assert_eq!(balances.holders_above(0), 0);
+
+        let (alice, bob, charlie, dave) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+            "dave".to_string(),
+        );
+        balances.set_balance(&dave, 50);
+        balances.set_balance(&charlie, 100);
+        balances.set_balance(&bob, 50);
+        balances.set_balance(&alice, 10);
+
+        // Bob and Dave tie, and are sorted by account.
+        assert_eq!(
+            balances.richest(3),
+            vec![
+                (charlie.clone(), 100),
+                (bob.clone(), 50),
+                (dave.clone(), 50)
+            ]
+        );
+        assert_eq!(
+            balances.richest(10),
+            vec![(charlie, 100), (bob, 50), (dave, 50), (alice, 10)]
+        );
+        assert_eq!(balances.holders_above(10), 3);
+        assert_eq!(balances.holders_above(50), 1);
+        assert_eq!(balances.holders_above(100), 0);
+    }
+
+    #[test]
+    fn transfer_with_tip_to_author() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob, carol) = ("alice".to_string(), "bob".to_string(), "carol".to_string());
+        balances.set_balance(&alice, 100);
+        balances.on_block_start(1, Some(&carol));
+        balances.take_events();
+
+        assert_eq!(
+            balances.transfer_with_tip(alice.clone(), bob.clone(), 30, 5),
+            Ok(())
+        );
+        assert_eq!(balances.balance(&alice), 65);
+        assert_eq!(balances.balance(&bob), 30);
+        assert_eq!(balances.balance(&carol), 5);
+        assert_eq!(balances.total_issuance(), 100); // The tip moved, it was not burned.
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                Event::Transfer {
+                    from: alice.clone(),
+                    to: bob,
+                    amount: 30
+                },
+                Event::Transfer {
+                    from: alice,
+                    to: carol,
+                    amount: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_with_tip_burned() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        balances.set_balance(&alice, 100);
+        balances.on_block_start(1, None); // No block author.
+        balances.take_events();
+
+        assert_eq!(
+            balances.transfer_with_tip(alice.clone(), bob.clone(), 30, 5),
+            Ok(())
+        );
+        assert_eq!(balances.balance(&alice), 65);
+        assert_eq!(balances.balance(&bob), 30);
+        assert_eq!(balances.total_issuance(), 95);
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                Event::Transfer {
+                    from: alice.clone(),
+                    to: bob,
+                    amount: 30
+                },
+                Event::Burned {
+                    who: alice,
+                    amount: 5
+                },
+            ]
+        );
+        assert_eq!(balances.try_state(1), Ok(()));
+    }
+
+    #[test]
+    fn transfer_with_tip_too_expensive() {
+        let mut balances = super::Pallet::<TestConfig>::new();
+        let (alice, bob, carol) = ("alice".to_string(), "bob".to_string(), "carol".to_string());
+        balances.set_balance(&alice, 100);
+        balances.on_block_start(1, Some(&carol));
+        balances.take_events();
+
+        // Alice can afford the transfer, but not the tip on top of it.
+        assert_eq!(
+            balances.transfer_with_tip(alice.clone(), bob.clone(), 100, 1),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(balances.balance(&alice), 100);
+        assert_eq!(balances.balance(&bob), 0);
+        assert_eq!(balances.balance(&carol), 0);
+        assert_eq!(balances.total_issuance(), 100);
+        assert_eq!(balances.take_events(), vec![]);
+
+        // A failing transfer never takes the tip.
+        assert_eq!(
+            balances.transfer_with_tip(alice.clone(), bob, 101, 1),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(balances.balance(&alice), 100);
+        assert_eq!(balances.balance(&carol), 0);
+    }
+
+    #[test]
+    fn dust_collector() {
+        let (alice, bob, treasury) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "treasury".to_string(),
+        );
+        let mut balances = super::Pallet::<DustConfig>::new_with_balances([
+            (alice.clone(), 100),
+            (treasury.clone(), 50),
+        ])
+        .unwrap();
+
+        // Alice is reaped, and her dust goes to the treasury instead of vanishing.
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 95), Ok(()));
+        assert!(!balances.account_exists(&alice));
+        assert_eq!(balances.balance(&treasury), 55);
+        assert_eq!(balances.collected_dust(), 5);
+        assert_eq!(balances.total_issuance(), 150);
+        assert_eq!(balances.try_state(0), Ok(()));
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                Event::DustCollected {
+                    who: alice.clone(),
+                    collector: treasury.clone(),
+                    amount: 5
+                },
+                Event::Transfer {
+                    from: alice,
+                    to: bob.clone(),
+                    amount: 95
+                },
+            ]
+        );
+
+        // Bob sends to the treasury, which also receives his dust.
+        assert_eq!(balances.transfer(bob.clone(), treasury.clone(), 90), Ok(()));
+        assert!(!balances.account_exists(&bob));
+        assert_eq!(balances.balance(&treasury), 150);
+        assert_eq!(balances.collected_dust(), 10);
+        assert_eq!(balances.total_issuance(), 150);
+        assert_eq!(balances.try_state(0), Ok(()));
+        assert!(balances.take_events().contains(&Event::DustCollected {
+            who: bob,
+            collector: treasury,
+            amount: 5
+        }));
+    }
+
+    #[test]
+    fn dust_collector_reaped() {
+        let (alice, treasury) = ("alice".to_string(), "treasury".to_string());
+        let mut balances =
+            super::Pallet::<DustConfig>::new_with_balances([(treasury.clone(), 50)]).unwrap();
+
+        // The treasury can not collect its own dust, so it is burned.
+        assert_eq!(
+            balances.transfer(treasury.clone(), alice.clone(), 45),
+            Ok(())
+        );
+        assert!(!balances.account_exists(&treasury));
+        assert_eq!(balances.collected_dust(), 0);
+        assert_eq!(balances.total_issuance(), 45);
+        assert!(balances.take_events().contains(&Event::DustLost {
+            who: treasury.clone(),
+            amount: 5
+        }));
+
+        // Dust which would leave the treasury below the existential deposit is burned too.
+        assert_eq!(balances.burn(alice.clone(), alice.clone(), 40), Ok(()));
+        assert!(!balances.account_exists(&alice));
+        assert!(!balances.account_exists(&treasury));
+        assert_eq!(balances.total_issuance(), 0);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn spending_limit() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+        balances.on_block_start(1, None);
+
+        assert_eq!(balances.set_spending_limit(alice.clone(), 30), Ok(()));
+        assert_eq!(balances.spending_limit(&alice), Some(30));
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 20), Ok(()));
+        assert_eq!(
+            balances.transfer(alice.clone(), bob.clone(), 11),
+            Err(BalancesError::SpendingLimitExceeded)
+        );
+        // A failing batch does not count against the limit.
+        assert_eq!(
+            balances.transfer_batch(alice.clone(), vec![(bob.clone(), 5), (bob.clone(), 500)]),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(balances.spent_this_block(&alice), 20);
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 10), Ok(()));
+        assert_eq!(balances.spent_this_block(&alice), 30);
+
+        // The limit starts over in the next block.
+        balances.on_block_start(2, None);
+        assert_eq!(balances.spent_this_block(&alice), 0);
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 30), Ok(()));
+
+        // Without the limit, Alice can send the rest at once.
+        assert_eq!(balances.clear_spending_limit(alice.clone()), Ok(()));
+        assert_eq!(balances.spending_limit(&alice), None);
+        assert_eq!(balances.transfer(alice.clone(), bob.clone(), 40), Ok(()));
+        assert_eq!(balances.balance(&bob), 100);
+    }
+
+    #[test]
+    fn transactions() {
+        let (alice, bob, charlie, treasury) = (
+            "alice".to_string(),
+            "bob".to_string(),
+            "charlie".to_string(),
+            "treasury".to_string(),
+        );
+        let mut balances = super::Pallet::<DustConfig>::new_with_balances([
+            (alice.clone(), 100),
+            (bob.clone(), 50),
+            (treasury.clone(), 20),
+        ])
+        .unwrap();
+        assert_eq!(balances.approve(alice.clone(), bob.clone(), 40), Ok(()));
+        let accounts = |balances: &super::Pallet<DustConfig>| {
+            balances
+                .accounts()
+                .map(|(who, balance)| (who.clone(), *balance))
+                .collect::<Vec<_>>()
+        };
+        let before = accounts(&balances);
+
+        balances.start_transaction();
+        assert_eq!(
+            balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 40),
+            Ok(())
+        );
+        assert_eq!(
+            balances.transfer(alice.clone(), charlie.clone(), 55),
+            Ok(())
+        ); // Alice is reaped.
+        assert_eq!(balances.reserve(&bob, 30), Ok(()));
+        assert_eq!(balances.burn(bob.clone(), bob.clone(), 10), Ok(()));
+        let during = accounts(&balances);
+        assert_eq!(balances.collected_dust(), 5);
+
+        // A nested transaction only undoes its own writes.
+        balances.start_transaction();
+        assert_eq!(balances.deposit(&charlie, 7), Ok(()));
+        balances.rollback_transaction();
+        assert_eq!(accounts(&balances), during);
+
+        // Once committed, the writes of a nested transaction are undone with the outer one.
+        balances.start_transaction();
+        assert_eq!(balances.deposit(&charlie, 7), Ok(()));
+        balances.commit_transaction();
+        balances.rollback_transaction();
+
+        assert_eq!(accounts(&balances), before);
+        assert_eq!(balances.total_issuance(), 170);
+        assert_eq!(balances.collected_dust(), 0);
+        assert_eq!(balances.reserved_balance(&bob), 0);
+        assert_eq!(balances.allowance(&alice, &bob), 40);
+        assert_eq!(balances.take_events(), vec![]);
+        assert_eq!(balances.try_state(0), Ok(()));
+
+        // Committed writes are kept.
+        balances.start_transaction();
+        assert_eq!(
+            balances.transfer(alice.clone(), charlie.clone(), 30),
+            Ok(())
+        );
+        balances.commit_transaction();
+        assert_eq!(balances.balance(&charlie), 30);
+        assert_eq!(balances.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn withdraw_reasons() {
+        const STAKING: LockIdentifier = *b"staking ";
+        let (alice, root) = ("alice".to_string(), "root".to_string());
+        let mut balances =
+            super::Pallet::<TestConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+        balances.set_root_account(Some(root.clone()));
+
+        // A frozen account can not send funds, but still pays fees and reserves.
+        assert_eq!(balances.freeze(root.clone(), alice.clone()), Ok(()));
+        assert_eq!(
+            balances.withdraw(&alice, 10, WithdrawReason::Transfer),
+            Err(BalancesError::FrozenAccount)
+        );
+        assert_eq!(balances.withdraw(&alice, 10, WithdrawReason::Fee), Ok(()));
+        assert_eq!(
+            balances.withdraw(&alice, 10, WithdrawReason::Reserve),
+            Ok(())
+        );
+        assert_eq!(balances.thaw(root, alice.clone()), Ok(()));
+
+        // Locked funds can only be slashed.
+        balances.set_lock(STAKING, &alice, 75);
+        for reason in [
+            WithdrawReason::Fee,
+            WithdrawReason::Transfer,
+            WithdrawReason::Reserve,
+        ] {
+            assert_eq!(
+                balances.withdraw(&alice, 10, reason),
+                Err(BalancesError::FundsLocked)
+            );
+        }
+        assert_eq!(balances.withdraw(&alice, 10, WithdrawReason::Slash), Ok(()));
+        assert_eq!(
+            balances.withdraw(&alice, 71, WithdrawReason::Slash),
+            Err(BalancesError::InsufficientFunds)
+        );
+
+        // Only the reserved funds are still part of the issuance.
+        assert_eq!(balances.balance(&alice), 70);
+        assert_eq!(balances.reserved_balance(&alice), 10);
+        assert_eq!(balances.total_issuance(), 80);
+        assert_eq!(balances.try_state(0), Ok(()));
+        let withdrawn = |reason| Event::Withdrawn {
+            who: alice.clone(),
+            amount: 10,
+            reason,
+        };
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                withdrawn(WithdrawReason::Fee),
+                withdrawn(WithdrawReason::Reserve),
+                withdrawn(WithdrawReason::Slash),
+            ]
+        );
+    }
+
+    #[test]
+    fn deposit() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let mut balances =
+            super::Pallet::<DepositConfig>::new_with_balances([(alice.clone(), u128::MAX - 10)])
+                .unwrap();
+
+        assert_eq!(balances.deposit(&alice, 11), Err(BalancesError::Overflow));
+        assert_eq!(
+            balances.deposit(&bob, 5),
+            Err(BalancesError::BelowExistentialDeposit)
+        );
+        assert_eq!(
+            balances.deposit(&bob, 11),
+            Err(BalancesError::IssuanceOverflow)
+        );
+        assert_eq!(
+            balances.withdraw(&alice, 100, WithdrawReason::Transfer),
+            Ok(())
+        );
+        assert_eq!(balances.deposit(&bob, 10), Ok(()));
+        assert_eq!(balances.total_issuance(), u128::MAX - 100);
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                Event::Withdrawn {
+                    who: alice,
+                    amount: 100,
+                    reason: WithdrawReason::Transfer
+                },
+                Event::Deposited {
+                    who: bob,
+                    amount: 10
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_is_withdraw_and_deposit() {
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+        let mut transferred =
+            super::Pallet::<DepositConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+        let mut moved =
+            super::Pallet::<DepositConfig>::new_with_balances([(alice.clone(), 100)]).unwrap();
+
+        // The same transfer, once as a call and once as its two halves. Alice is reaped either way.
+        assert_eq!(transferred.transfer(alice.clone(), bob.clone(), 95), Ok(()));
+        assert_eq!(moved.withdraw(&alice, 95, WithdrawReason::Transfer), Ok(()));
+        assert_eq!(moved.deposit(&bob, 95), Ok(()));
+        assert_eq!(
+            transferred.accounts().collect::<Vec<_>>(),
+            moved.accounts().collect::<Vec<_>>()
+        );
+        assert_eq!(transferred.total_issuance(), moved.total_issuance());
+
+        // Both check the sender before the receiver.
+        assert_eq!(
+            transferred.transfer(alice.clone(), bob.clone(), 1),
+            Err(BalancesError::InsufficientFunds)
+        );
+        assert_eq!(
+            moved.withdraw(&alice, 1, WithdrawReason::Transfer),
+            Err(BalancesError::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn check_config() {
+        assert!(super::Pallet::<DustConfig>::check_config().is_empty());
+
+        // Reaping is disabled, so the dust collector can never collect anything.
+        assert_eq!(
+            super::Pallet::<BaseConfig<0, Treasury>>::check_config(),
+            vec![(
+                ConfigSeverity::Error,
+                "dust collector set, but the existential deposit is zero so no account is reaped"
+            )]
+        );
+        assert_eq!(
+            super::Pallet::<BaseConfig<10, (), (), 11>>::check_config(),
+            vec![(
+                ConfigSeverity::Warning,
+                "transaction fee is above the existential deposit, accounts holding it can not pay"
+            )]
+        );
+
+        // The genesis values are checked against the constants too.
+        let genesis = |minimum_transfer| super::GenesisConfig::<DepositConfig> {
+            balances: vec![],
+            minimum_transfer,
+        };
+        assert!(genesis(0).check_config().is_empty());
+        assert!(genesis(10).check_config().is_empty());
+        assert_eq!(
+            genesis(5).check_config(),
+            vec![(
+                ConfigSeverity::Warning,
+                "minimum transfer is below the existential deposit, which new accounts need anyway"
+            )]
         );
     }
 }
@@ -1,10 +1,11 @@
 // main.rs serves as the primary entry point for setting up the blockchain runtime.
 // It links various modules, including pallets that form the components of the runtime.
 
+mod activity; // Statistics of the transfers of each account, fed by the balances hooks.
 mod balances; // Balance management for accounts and allows them to transfer.
-mod proof_of_existence;
+mod proof_of_existence; // Pallet for managing proofs of data existence.
 mod support; // Support types and traits used across the runtime.
-mod system; // Core system functionality for the blockchain. // Pallet for managing proofs of data existence.
+mod system; // Core system functionality for the blockchain.
 use crate::support::Dispatch; // Interface for dispatching calls.
 
 // These are the concrete types we will use in our simple state machine.
@@ -15,10 +16,11 @@ mod types {
     pub type Balance = u128; // Supports high precision for account balances.
     pub type BlockNumber = u32; // Tracks the sequence of blocks.
     pub type Nonce = u32; // Nonce to ensure transaction uniqueness.
+    pub type AssetId = u32; // Identifies the tokens which exist next to the native one.
     pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall>; // A call a user makes. composed of a Call (the function we will execute) and a Caller (the account that wants to execute that function).
     pub type Header = crate::support::Header<BlockNumber>; // Contains metadata about the block which is used to verify that the block is valid ( block number, Parent Hash, State Root).
     pub type Block = crate::support::Block<Header, Extrinsic>; // Two parts: the header and a vector of extrinsics.
-    pub type Content = &'static str; // Static reference to data content, used in proofs.
+    pub type Content = String; // The data content claimed in proofs, which can be read at runtime.
 }
 
 
@@ -27,6 +29,7 @@ mod types {
 pub struct Runtime {
     system: system::Pallet<Self>,     // This is the system pallet.
     balances: balances::Pallet<Self>, // This is the balances pallet.
+    #[currency(balances)] // The PoE calls which take a currency are given the balances pallet.
     proof_of_existence: proof_of_existence::Pallet<Self>, // This is the PoE pallet.
 }
 
@@ -35,28 +38,150 @@ impl system::Config for Runtime {
     type AccountId = types::AccountId;
     type BlockNumber = types::BlockNumber;
     type Nonce = types::Nonce;
+    const IDEMPOTENCY_WINDOW: types::BlockNumber = 10;
+    type RuntimeEvent = RuntimeEvent; // Generated by the runtime macro from the events of each pallet.
 }
 
 // Implementing the balances pallet in the runtime, makig it configurable and generic.
 impl balances::Config for Runtime {
     type Balance = types::Balance;
+    const EXISTENTIAL_DEPOSIT: types::Balance = 1;
+    type DustCollector = (); // The dust of reaped accounts is burned.
+    type TransferHooks = activity::ActivityStatistics<types::AccountId, types::Balance>; // Counts the transfers of each account.
+    const MAX_TRANSFER_POLICY_LEN: usize = 16;
+    type AssetId = types::AssetId;
+    const TRANSACTION_FEE: types::Balance = 1;
+    const MAX_MEMO_LEN: usize = 64;
+}
+
+// Every extrinsic pays the flat transaction fee of the balances pallet.
+impl support::ChargeFee<types::AccountId> for Runtime {
+    fn charge_fee(&mut self, who: &types::AccountId) -> support::DispatchResult {
+        self.balances
+            .withdraw_fee(who, <Runtime as balances::Config>::TRANSACTION_FEE)
+            .map_err(Into::into)
+    }
 }
 
 // Implementing the PoE pallet in the runtime, makig it configurable and generic.
 impl proof_of_existence::Config for Runtime {
     type Content = types::Content;
+    type Normalizer = proof_of_existence::Identity; // Content is claimed exactly as it is.
+    type Hasher = proof_of_existence::Fnv64; // Claims are stored under the hash of their content.
+    type Currency = balances::Pallet<Runtime>;
+    const CLAIM_DEPOSIT: types::Balance = 10;
+    const MAX_BATCH_CLAIMS: usize = 100;
+    const MAX_HISTORY_LEN: usize = 32;
+    const CLAIM_TTL: types::BlockNumber = 1000; // Claims must be renewed every 1000 blocks.
+    const MAX_METADATA_LEN: usize = 256;
+}
+
+// The initial state of every pallet which needs one.
+struct RuntimeGenesisConfig {
+    balances: balances::GenesisConfig<Runtime>,
+}
+
+impl Runtime {
+    // Create a new runtime and apply the genesis configuration, before any block executes. Like
+    // `new`, it refuses configuration errors, including those of the genesis values. All problems
+    // are logged, and the first error is returned.
+    fn new_from_genesis(genesis: RuntimeGenesisConfig) -> Result<Self, &'static str> {
+        let first_error = |errors: Vec<support::ConfigWarning>| errors[0].message;
+        let mut runtime = Self::new().map_err(first_error)?;
+        let genesis_warnings = genesis.balances.check_config().into_iter().map(|(severity, message)| {
+            support::ConfigWarning { pallet: "balances", severity, message }
+        });
+        support::check_configuration(genesis_warnings.collect()).map_err(first_error)?;
+        genesis.balances.build(&mut runtime.balances)?;
+        Ok(runtime)
+    }
+
+    // Check that `claim` was claimed by `owner` at or before block `at_or_before`.
+    fn verify_claim(
+        &self,
+        claim: &types::Content,
+        owner: &types::AccountId,
+        at_or_before: types::BlockNumber,
+    ) -> Result<(), proof_of_existence::VerifyError> {
+        self.proof_of_existence.verify_claim(claim, owner, at_or_before)
+    }
 }
 
+// Render the help of the call named `call` in the pallet named `pallet`, from the doc comments
+// captured by `#[macros::call]`: the description, then one line per argument.
+fn call_help(pallet: &str, call: &str) -> Option<String> {
+    let (docs, args_docs) = match pallet {
+        "balances" => (balances::Call::<Runtime>::docs(), balances::Call::<Runtime>::args_docs(call)),
+        "proof_of_existence" => (proof_of_existence::Call::<Runtime>::docs(), proof_of_existence::Call::<Runtime>::args_docs(call)),
+        _ => return None,
+    };
+    let (_, doc) = docs.iter().find(|(name, _)| *name == call)?;
+    // The argument lines of the doc are rendered with the other arguments below.
+    let description = doc.lines().filter(|line| !line.trim_start().starts_with("- `")).collect::<Vec<_>>();
+    let mut help = format!("{pallet} {call}\n{}\n", description.join("\n").trim_end());
+    if !args_docs.is_empty() {
+        help.push_str("\nArguments:\n");
+        for (name, doc) in args_docs {
+            help.push_str(&format!("  {name}: {doc}\n"));
+        }
+    }
+    Some(help)
+}
+
+// What the command line asks for.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help { pallet: String, call: String }, // `help <pallet> <call>`: print the docs of a call.
+    Demo { message: String },              // `claim <text>`, or nothing: run the demo claiming the text.
+}
+
+// Parse the arguments following the program name. Unknown commands are rejected, so a typo like
+// `hlep` is not claimed as content.
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    let arg = |index: usize| args.get(index).cloned().unwrap_or_default();
+    match args.first().map(String::as_str) {
+        None => Ok(Command::Demo { message: "Hello, world!".to_string() }),
+        Some("help") => Ok(Command::Help { pallet: arg(1), call: arg(2) }),
+        Some("claim") if args.len() > 1 => Ok(Command::Demo { message: args[1..].join(" ") }),
+        Some("claim") => Err("usage: claim <text>".to_string()),
+        Some(command) => Err(format!("unknown command `{command}`, expected `help <pallet> <call>` or `claim <text>`")),
+    }
+}
 
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let message = match parse_command(&args) {
+        Ok(Command::Demo { message }) => message,
+        Ok(Command::Help { pallet, call }) => {
+            match call_help(&pallet, &call) {
+                Some(help) => print!("{help}"),
+                None => eprintln!("unknown call `{call}` in pallet `{pallet}`"),
+            }
+            return;
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    };
+
     // Main function to instantiate the runtime and execute blocks.
-    let mut runtime = Runtime::new(); // Mutable runtime.
     let alice = "alice".to_string(); // Asigns wallet address to alice.
     let bob = "bob".to_string(); // Asigns wallet address to bob.
     let charlie = "charlie".to_string(); // Asigns wallet address to charlie.
+    let dave = "dave".to_string(); // Asigns wallet address to dave.
+    // The content alice and bob claim is the `message` given to `claim`, or "Hello, world!".
 
-    // Initializes the system.
-    runtime.balances.set_balance(&alice, 100); // sets alice's balance to 100
+    // Initializes the system, with alice starting at a balance of 100, and dave at 5.
+    // Transfers below 5 are rejected to prevent spam.
+    let genesis = RuntimeGenesisConfig {
+        balances: balances::GenesisConfig {
+            balances: vec![(alice.clone(), 100), (dave.clone(), 5)],
+            minimum_transfer: 5,
+        },
+    };
+    let mut runtime = Runtime::new_from_genesis(genesis).expect("invalid genesis"); // Mutable runtime.
+    runtime.system.set_block_author(Some(charlie.clone())); // Charlie authors the blocks, and receives the tips.
 
     // Here are the extrinsics in our block.
     // You can add or remove these based on the modules and calls you have set up.
@@ -70,13 +195,34 @@ fn main() {
                     to: bob.clone(),
                     amount: 20,
                 }),
+                idempotency_key: None,
             },
             support::Extrinsic {
                 caller: alice.clone(),
                 call: RuntimeCall::balances(balances::Call::transfer {
-                    to: charlie,
+                    to: charlie.clone(),
                     amount: 20,
                 }),
+                idempotency_key: None,
+            },
+            // Alice tips the block author to get her transfer in.
+            support::Extrinsic {
+                caller: alice.clone(),
+                call: RuntimeCall::balances(balances::Call::transfer_with_tip {
+                    to: bob.clone(),
+                    amount: 10,
+                    tip: 5,
+                }),
+                idempotency_key: None,
+            },
+            // This one is below the minimum transfer, so it is rejected.
+            support::Extrinsic {
+                caller: alice.clone(),
+                call: RuntimeCall::balances(balances::Call::transfer {
+                    to: charlie.clone(),
+                    amount: 3,
+                }),
+                idempotency_key: None,
             },
         ],
     };
@@ -85,23 +231,33 @@ fn main() {
     let block_2 = types::Block {
         header: support::Header { block_number: 2 },
         extrinsics: vec![
-            // The first extrinsic involves Alice creating a claim on the string "Hello, world!".
+            // The first extrinsic involves Alice creating a claim on the message.
             // This operation registers a proof of existence claim in the blockchain's state,
-            // asserting that the content "Hello, world!" was claimed at block number 2 by Alice.
+            // asserting that the message was claimed at block number 2 by Alice.
             support::Extrinsic {
                 caller: alice.clone(),
                 call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-                    claim: &"Hello, world!",
+                    claim: message.clone(),
                 }),
+                idempotency_key: None,
             },
             // The second extrinsic similarly involves Bob attempting to create a claim on the
-            // same string "Hello, world!". If Alice's claim was successfully registered,
+            // same message. If Alice's claim was successfully registered,
             // Bob's claim should fail because the content is already claimed.
             support::Extrinsic {
                 caller: bob.clone(),
                 call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-                    claim: &"Hello, world!",
+                    claim: message.clone(),
                 }),
+                idempotency_key: None,
+            },
+            // Dave can pay the fee, but not the claim deposit, so his claim fails.
+            support::Extrinsic {
+                caller: dave,
+                call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+                    claim: "Goodbye, world!".to_string(),
+                }),
+                idempotency_key: None,
             },
         ],
     };
@@ -110,24 +266,32 @@ fn main() {
     let block_3 = types::Block {
         header: support::Header { block_number: 3 },
         extrinsics: vec![
-            // The first extrinsic in this block involves Alice revoking her claim on "Hello, world!".
+            // The first extrinsic in this block involves Alice revoking her claim on the message.
             // This action, if successful, removes the claim from the state, indicating that the content
-            // is no longer claimed by Alice. This could be useful for relinquishing rights or correcting
-            // an erroneous claim.
+            // is no longer claimed by Alice, and returns her claim deposit. This could be useful for
+            // relinquishing rights or correcting an erroneous claim.
             support::Extrinsic {
-                caller: alice,
+                caller: alice.clone(),
                 call: RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-                    claim: &"Hello, world!",
+                    claim: message.clone(),
                 }),
+                idempotency_key: None,
             },
-            // Following Alice's revocation, Bob attempts to create a claim again on "Hello, world!".
+            // Following Alice's revocation, Bob attempts to create a claim again on the message.
             // If Alice's revocation was successful, Bob should now be able to register the claim
             // under his name, effectively taking ownership of the proof of existence for this content.
             support::Extrinsic {
-                caller: bob,
+                caller: bob.clone(),
                 call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-                    claim: &"Hello, world!",
+                    claim: message.clone(),
                 }),
+                idempotency_key: None,
+            },
+            // Finally, Charlie empties his account by sending everything he has to Bob.
+            support::Extrinsic {
+                caller: charlie,
+                call: RuntimeCall::balances(balances::Call::transfer_all { to: bob }),
+                idempotency_key: None,
             },
         ],
     };
@@ -138,10 +302,597 @@ fn main() {
 
     // Execute the extrinsics which make up our block.
     // If there are any errors, our system panics, since we should not execute invalid blocks.
-    runtime.execute_block(block_1).expect("invalid block");
-    runtime.execute_block(block_2).expect("invalid block");
-    runtime.execute_block(block_3).expect("invalid block");
+    // The events of each block are printed once it is executed.
+    for block in [block_1, block_2, block_3] {
+        let block_number = block.header.block_number;
+        runtime.execute_block(block).expect("invalid block");
+        println!("Block {} events: {:#?}", block_number, runtime.system.events());
+        if let Some((owner, claimed_at)) = runtime.proof_of_existence.get_claim(&message) {
+            println!("{:?} is claimed by {} since block {}", message, owner, claimed_at);
+        }
+    }
+
+    // Anyone can check who claimed the message, and by which block.
+    let verified = runtime.verify_claim(&message, &"bob".to_string(), 3);
+    println!("Bob claimed {:?} by block 3: {:?}", message, verified);
+
+    // The balances pallet counted the transfers of each account as they went through.
+    println!("Alice's activity: {:?}", runtime.balances.hooks().activity(&alice));
+
+    // Only the hashes of claimed content are stored: different content is claimed under different
+    // hashes, while claiming the same content again collides, as Bob found out in block 2.
+    for content in [message, "Goodbye, world!".to_string()] {
+        let hash = proof_of_existence::Pallet::<Runtime>::content_hash(content.clone());
+        println!("{:?} hashes to {:02x?}", content, hash);
+    }
 
     // Simply print the debug format of our runtime state.
     println!("{:#?}", runtime);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn execute_empty_blocks() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+
+        assert_eq!(runtime.execute_empty_blocks_until(10), Ok(())); // Produces blocks 1 to 10.
+        assert_eq!(runtime.system.block_number(), 10);
+
+        assert_eq!(runtime.execute_empty_blocks_until(5), Ok(())); // Already past block 5, nothing happens.
+        assert_eq!(runtime.system.block_number(), 10);
+
+        // Normal blocks can follow the empty ones.
+        let block = types::Block { header: support::Header { block_number: 11 }, extrinsics: vec![] };
+        assert_eq!(runtime.execute_block(block), Ok(()));
+        assert_eq!(runtime.system.block_number(), 11);
+    }
+
+    #[test]
+    fn try_state_all() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        runtime.balances.set_balance(&alice, 100);
+
+        // Blocks like the demo ones. `execute_block` runs every check after each block in tests.
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![support::Extrinsic {
+                caller: alice.clone(),
+                call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 90 }),
+                idempotency_key: None,
+            }],
+        };
+        let block_2 = types::Block {
+            header: support::Header { block_number: 2 },
+            extrinsics: vec![support::Extrinsic {
+                caller: bob.clone(),
+                call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+                    claim: "Hello, world!".to_string(),
+                }),
+                idempotency_key: None,
+            }],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+        runtime.execute_block(block_2).expect("invalid block");
+        assert_eq!(runtime.try_state_all(), Ok(()));
+
+        // A broken invariant names the pallet and the invariant.
+        runtime.balances.insert_raw_balance(&alice, 0);
+        assert_eq!(runtime.try_state_all(), Err(("balances", "zero balance stored")));
+    }
+
+    #[test]
+    fn duplicate_idempotency_key() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        runtime.balances.set_balance(&alice, 100);
+
+        // The gateway retried the same transfer with the same key.
+        let transfer = |key| support::Extrinsic {
+            caller: alice.clone(),
+            call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+            idempotency_key: Some(key),
+        };
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![transfer([1; 16]), transfer([1; 16]), transfer([2; 16])],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+
+        assert_eq!(runtime.balances.balance(&bob), 20); // The duplicate was rejected, the other key went through.
+        assert_eq!(runtime.system.nonce(&alice), 2); // The duplicate did not use a nonce.
+    }
+
+    #[test]
+    fn idempotency_key_after_fee_failure() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let transfer = || support::Extrinsic {
+            caller: alice.clone(),
+            call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+            idempotency_key: Some([1; 16]),
+        };
+
+        // Alice can not pay the fee, so the extrinsic is rejected without using its key.
+        let block_1 = types::Block { header: support::Header { block_number: 1 }, extrinsics: vec![transfer()] };
+        runtime.execute_block(block_1).expect("invalid block");
+        assert_eq!(runtime.system.nonce(&alice), 0);
+
+        // Once funded, she can send it again with the same key.
+        runtime.balances.set_balance(&alice, 100);
+        let block_2 = types::Block { header: support::Header { block_number: 2 }, extrinsics: vec![transfer()] };
+        runtime.execute_block(block_2).expect("invalid block");
+        assert_eq!(runtime.balances.balance(&bob), 10);
+        assert_eq!(runtime.system.nonce(&alice), 1);
+    }
+
+    #[test]
+    fn validate_configuration() {
+        assert_eq!(Runtime::validate_configuration(), vec![]); // The demo runtime is consistent.
+
+        // A runtime starts despite warnings, but not with errors, which are handed back.
+        let warning = || support::ConfigWarning {
+            pallet: "system",
+            severity: support::ConfigSeverity::Warning,
+            message: "idempotency window is zero, duplicate keys are never rejected",
+        };
+        let error = || support::ConfigWarning {
+            pallet: "proof_of_existence",
+            severity: support::ConfigSeverity::Error,
+            message: "batch limit is zero, `create_claims` always fails",
+        };
+        assert_eq!(support::check_configuration(vec![warning()]), Ok(()));
+        assert_eq!(support::check_configuration(vec![warning(), error()]), Err(vec![error()]));
+    }
+
+    // `help <pallet> <call>` shows the doc of the call and of each of its arguments.
+    #[test]
+    fn call_help() {
+        let help = super::call_help("balances", "transfer").expect("transfer is a call");
+        assert!(help.starts_with("balances transfer\nTransfer `amount` from one account to another."));
+        assert!(help.contains("  to: The account ID of the receiver.\n"));
+        assert!(help.contains("  amount: The amount being sent to the receiver.\n"));
+        assert!(super::call_help("proof_of_existence", "create_claim").is_some());
+        assert_eq!(super::call_help("balances", "unknown"), None);
+        assert_eq!(super::call_help("unknown", "transfer"), None);
+    }
+
+    // Only `help` and `claim` are commands, anything else is an error rather than content.
+    #[test]
+    fn parse_command() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let demo = |message: &str| Ok(super::Command::Demo { message: message.to_string() });
+        assert_eq!(super::parse_command(&args(&[])), demo("Hello, world!"));
+        assert_eq!(super::parse_command(&args(&["claim", "my", "text"])), demo("my text"));
+        assert_eq!(super::parse_command(&args(&["claim", "help"])), demo("help"));
+        assert_eq!(
+            super::parse_command(&args(&["help", "balances", "transfer"])),
+            Ok(super::Command::Help { pallet: "balances".to_string(), call: "transfer".to_string() })
+        );
+        assert_eq!(super::parse_command(&args(&["claim"])), Err("usage: claim <text>".to_string()));
+        assert_eq!(
+            super::parse_command(&args(&["hlep"])),
+            Err("unknown command `hlep`, expected `help <pallet> <call>` or `claim <text>`".to_string())
+        );
+    }
+
+    // The activity statistics only count the transfers which were not rolled back.
+    #[test]
+    fn activity_statistics() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        runtime.balances.set_balance(&alice, 100);
+
+        let transfer_batch = |recipients| support::Extrinsic {
+            caller: alice.clone(),
+            call: RuntimeCall::balances(balances::Call::transfer_batch { recipients }),
+            idempotency_key: None,
+        };
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![
+                // The first transfer goes through before the second fails, and is rolled back.
+                transfer_batch(vec![(bob.clone(), 10), (bob.clone(), 500)]),
+                transfer_batch(vec![(bob.clone(), 10), (bob.clone(), 20)]),
+            ],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+
+        let activity = runtime.balances.hooks().activity(&alice);
+        assert_eq!(activity, activity::AccountActivity { sent: 2, received: 0, volume_sent: 30, volume_received: 0 });
+        assert_eq!(runtime.balances.hooks().activity(&bob).received, 2);
+    }
+
+    #[test]
+    fn block_events() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        runtime.balances.set_balance(&alice, 100); // Not part of any block.
+
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![
+                support::Extrinsic {
+                    caller: alice.clone(),
+                    call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+                    idempotency_key: None,
+                },
+                support::Extrinsic {
+                    caller: alice.clone(),
+                    call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 500 }),
+                    idempotency_key: None,
+                },
+                support::Extrinsic {
+                    caller: bob.clone(),
+                    call: RuntimeCall::balances(balances::Call::burn { from: bob.clone(), amount: 10 }),
+                    idempotency_key: None,
+                },
+            ],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+
+        // Every extrinsic paid its fee, and the failed transfer raised no other event.
+        let fee = |who: &types::AccountId| {
+            RuntimeEvent::balances(balances::Event::Withdrawn { who: who.clone(), amount: 1, reason: balances::WithdrawReason::Fee })
+        };
+        assert_eq!(
+            runtime.system.events(),
+            &[
+                fee(&alice),
+                RuntimeEvent::balances(balances::Event::Transfer { from: alice.clone(), to: bob.clone(), amount: 30 }),
+                fee(&alice),
+                fee(&bob),
+                RuntimeEvent::balances(balances::Event::Burned { who: bob, amount: 10 }),
+            ]
+        );
+
+        // Events are kept per block.
+        assert_eq!(runtime.execute_empty_blocks_until(2), Ok(()));
+        assert_eq!(runtime.system.events(), &[]);
+    }
+
+    #[test]
+    fn new_from_genesis() {
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let genesis = RuntimeGenesisConfig {
+            balances: balances::GenesisConfig {
+                balances: vec![(alice.clone(), 100), (bob.clone(), 50)],
+                minimum_transfer: 0,
+            },
+        };
+        let runtime = Runtime::new_from_genesis(genesis).expect("invalid genesis");
+        assert_eq!(runtime.balances.balance(&alice), 100);
+        assert_eq!(runtime.balances.balance(&bob), 50);
+        assert_eq!(runtime.balances.total_issuance(), 150);
+        assert_eq!(runtime.try_state_all(), Ok(()));
+
+        // An account listed twice is rejected.
+        let genesis = RuntimeGenesisConfig {
+            balances: balances::GenesisConfig {
+                balances: vec![(alice.clone(), 100), (alice, 50)],
+                minimum_transfer: 0,
+            },
+        };
+        assert_eq!(Runtime::new_from_genesis(genesis).err(), Some("duplicate account in genesis"));
+    }
+
+    #[test]
+    fn transaction_fees() {
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let genesis = RuntimeGenesisConfig {
+            balances: balances::GenesisConfig {
+                balances: vec![(alice.clone(), 100), (bob.clone(), 1)],
+                minimum_transfer: 0,
+            },
+        };
+        let mut runtime = Runtime::new_from_genesis(genesis).expect("invalid genesis");
+
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![
+                // Alice could send all her funds, but not once the fee is paid. The fee is kept.
+                support::Extrinsic {
+                    caller: alice.clone(),
+                    call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 100 }),
+                    idempotency_key: None,
+                },
+                // Bob can only pay the fee, so his failing claim still costs him all he has.
+                support::Extrinsic {
+                    caller: bob.clone(),
+                    call: RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
+                        claim: "Hello, world!".to_string(),
+                    }),
+                    idempotency_key: None,
+                },
+                // Bob can not pay the fee anymore, so this is rejected before it does anything.
+                support::Extrinsic {
+                    caller: bob.clone(),
+                    call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+                        claim: "Hello, world!".to_string(),
+                    }),
+                    idempotency_key: None,
+                },
+            ],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+
+        assert_eq!(runtime.balances.balance(&alice), 99);
+        assert_eq!(runtime.balances.balance(&bob), 0);
+        assert_eq!(runtime.balances.collected_fees(), 2);
+        assert_eq!(runtime.balances.total_issuance(), 101);
+        assert_eq!(runtime.proof_of_existence.get_claim(&"Hello, world!".to_string()), None);
+        assert_eq!(runtime.proof_of_existence.claim_count(), 0);
+        assert_eq!(runtime.system.nonce(&bob), 1); // The rejected extrinsic did not use a nonce.
+    }
+
+    #[test]
+    fn force_set_balance() {
+        let root = "root".to_string();
+        let alice = "alice".to_string();
+        let genesis = RuntimeGenesisConfig {
+            balances: balances::GenesisConfig {
+                balances: vec![(root.clone(), 10), (alice.clone(), 10)],
+                minimum_transfer: 0,
+            },
+        };
+        let mut runtime = Runtime::new_from_genesis(genesis).expect("invalid genesis");
+        runtime.balances.set_root_account(Some(root.clone()));
+
+        let force_set_balance = |caller: &types::AccountId, amount| support::Extrinsic {
+            caller: caller.clone(),
+            call: RuntimeCall::balances(balances::Call::force_set_balance { who: alice.clone(), amount }),
+            idempotency_key: None,
+        };
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![force_set_balance(&alice, 1_000), force_set_balance(&root, 500)],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+
+        // Only the root account could set the balance. Alice's fee was taken before root's call.
+        assert_eq!(runtime.balances.balance(&alice), 500);
+        assert_eq!(runtime.balances.total_issuance(), 500 + 9 + 2);
+        let fee = |who: &types::AccountId| {
+            RuntimeEvent::balances(balances::Event::Withdrawn { who: who.clone(), amount: 1, reason: balances::WithdrawReason::Fee })
+        };
+        assert_eq!(
+            runtime.system.events(),
+            &[fee(&alice), fee(&root), RuntimeEvent::balances(balances::Event::BalanceSet { who: alice.clone(), amount: 500 })]
+        );
+    }
+
+    #[test]
+    fn block_author_tips() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let charlie = "charlie".to_string();
+        runtime.balances.set_balance(&alice, 100);
+
+        // The author set in the system pallet is paid the tips of the next blocks.
+        runtime.system.set_block_author(Some(charlie.clone()));
+        assert_eq!(runtime.system.block_author(), Some(&charlie));
+        let tipped_transfer = || support::Extrinsic {
+            caller: alice.clone(),
+            call: RuntimeCall::balances(balances::Call::transfer_with_tip { to: bob.clone(), amount: 10, tip: 5 }),
+            idempotency_key: None,
+        };
+        let block_1 = types::Block { header: support::Header { block_number: 1 }, extrinsics: vec![tipped_transfer()] };
+        runtime.execute_block(block_1).expect("invalid block");
+        assert_eq!(runtime.balances.balance(&alice), 100 - 1 - 10 - 5); // The fee, the amount and the tip.
+        assert_eq!(runtime.balances.balance(&charlie), 5);
+
+        // Without an author, the tip is burned.
+        runtime.system.set_block_author(None);
+        let block_2 = types::Block { header: support::Header { block_number: 2 }, extrinsics: vec![tipped_transfer()] };
+        runtime.execute_block(block_2).expect("invalid block");
+        assert_eq!(runtime.balances.balance(&charlie), 5);
+        assert_eq!(runtime.balances.total_issuance(), 100 - 5); // Fees are collected, only the tip is gone.
+    }
+
+    #[test]
+    fn spending_limit() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        runtime.balances.set_balance(&alice, 100);
+
+        let extrinsic = |call| support::Extrinsic { caller: alice.clone(), call: RuntimeCall::balances(call), idempotency_key: None };
+        let transfer = |amount| extrinsic(balances::Call::transfer { to: bob.clone(), amount });
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![extrinsic(balances::Call::set_spending_limit { limit: 25 }), transfer(20), transfer(10)],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+        assert_eq!(runtime.balances.balance(&bob), 20); // The second transfer went over the limit.
+        assert_eq!(runtime.balances.spent_this_block(&alice), 20);
+
+        // The counter is reset when the next block starts.
+        let block_2 = types::Block { header: support::Header { block_number: 2 }, extrinsics: vec![transfer(10), transfer(10)] };
+        runtime.execute_block(block_2).expect("invalid block");
+        assert_eq!(runtime.balances.balance(&bob), 40);
+        assert_eq!(runtime.balances.spent_this_block(&alice), 20);
+    }
+
+    #[test]
+    fn claim_offers() {
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let charlie = "charlie".to_string();
+        let genesis = RuntimeGenesisConfig {
+            balances: balances::GenesisConfig {
+                balances: vec![(alice.clone(), 100), (bob.clone(), 100)],
+                minimum_transfer: 0,
+            },
+        };
+        let mut runtime = Runtime::new_from_genesis(genesis).expect("invalid genesis");
+        let poe = |caller: &types::AccountId, call| support::Extrinsic {
+            caller: caller.clone(),
+            call: RuntimeCall::proof_of_existence(call),
+            idempotency_key: None,
+        };
+        let content = "Hello, world!".to_string();
+
+        // Alice claims the content, and bob offers 50 for it.
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![
+                poe(&alice, proof_of_existence::Call::create_claim { claim: content.clone() }),
+                poe(&bob, proof_of_existence::Call::make_offer { claim: content.clone(), price: 50 }),
+            ],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+        assert_eq!(runtime.balances.reserved_balance(&bob), 50);
+
+        // Offers on unclaimed content, offers nobody can pay and missing offers are rejected.
+        let make_offer = |claim: &str, price| {
+            RuntimeCall::proof_of_existence(proof_of_existence::Call::make_offer { claim: claim.to_string(), price })
+        };
+        assert_eq!(runtime.dispatch(charlie.clone(), make_offer("Goodbye, world!", 10)), Err("claim does not exist"));
+        assert_eq!(runtime.dispatch(charlie.clone(), make_offer(&content, 10)), Err("cannot afford offer"));
+        let accept = RuntimeCall::proof_of_existence(proof_of_existence::Call::accept_offer {
+            claim: content.clone(),
+            buyer: charlie,
+        });
+        assert_eq!(runtime.dispatch(alice.clone(), accept), Err("offer does not exist"));
+
+        // Alice sells the claim to bob, and receives his escrowed price.
+        let block_2 = types::Block {
+            header: support::Header { block_number: 2 },
+            extrinsics: vec![poe(&alice, proof_of_existence::Call::accept_offer { claim: content.clone(), buyer: bob.clone() })],
+        };
+        runtime.execute_block(block_2).expect("invalid block");
+        assert_eq!(runtime.proof_of_existence.get_claim(&content), Some(&(bob.clone(), 1)));
+        assert_eq!(runtime.balances.balance(&alice), 100 - 2 - 10 + 50); // Two fees and the claim deposit.
+        assert_eq!(runtime.balances.balance(&bob), 100 - 1 - 50);
+        assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+        assert!(runtime.system.events().contains(&RuntimeEvent::proof_of_existence(
+            proof_of_existence::Event::ClaimOfferAccepted {
+                seller: alice,
+                buyer: bob,
+                claim: proof_of_existence::Pallet::<Runtime>::content_hash(content),
+                price: 50,
+            }
+        )));
+    }
+
+    // The seller's transfer policy still decides who it receives funds from, escrowed or not.
+    #[test]
+    fn offer_blocked_by_transfer_policy() {
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let genesis = RuntimeGenesisConfig {
+            balances: balances::GenesisConfig {
+                balances: vec![(alice.clone(), 100), (bob.clone(), 100)],
+                minimum_transfer: 0,
+            },
+        };
+        let mut runtime = Runtime::new_from_genesis(genesis).expect("invalid genesis");
+        let content = "Hello, world!".to_string();
+        let poe = |call| RuntimeCall::proof_of_existence(call);
+
+        assert_eq!(runtime.dispatch(alice.clone(), poe(proof_of_existence::Call::create_claim { claim: content.clone() })), Ok(()));
+        assert_eq!(runtime.dispatch(bob.clone(), poe(proof_of_existence::Call::make_offer { claim: content.clone(), price: 50 })), Ok(()));
+        let policy = balances::Call::set_transfer_policy { mode: balances::TransferMode::DenyListed, list: vec![bob.clone()] };
+        assert_eq!(runtime.dispatch(alice.clone(), RuntimeCall::balances(policy)), Ok(()));
+
+        // Alice can not be paid by bob, so the sale fails and the offer stays escrowed.
+        let accept = proof_of_existence::Call::accept_offer { claim: content.clone(), buyer: bob.clone() };
+        assert_eq!(runtime.dispatch(alice.clone(), poe(accept)), Err("offer payment failed"));
+        assert_eq!(runtime.proof_of_existence.get_claim(&content), Some(&(alice.clone(), 0)));
+        assert_eq!(runtime.proof_of_existence.offer(&content, &bob), Some(50));
+        assert_eq!(runtime.balances.reserved_balance(&bob), 50);
+        assert_eq!(runtime.balances.balance(&alice), 100 - 10);
+        assert_eq!(runtime.try_state_all(), Ok(()));
+    }
+
+    // An accepted offer is paid out of its escrow, so it does not count as a transfer of the buyer.
+    #[test]
+    fn offer_below_minimum_transfer() {
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let genesis = RuntimeGenesisConfig {
+            balances: balances::GenesisConfig {
+                balances: vec![(alice.clone(), 100), (bob.clone(), 100)],
+                minimum_transfer: 20,
+            },
+        };
+        let mut runtime = Runtime::new_from_genesis(genesis).expect("invalid genesis");
+        let poe = |caller: &types::AccountId, call| support::Extrinsic {
+            caller: caller.clone(),
+            call: RuntimeCall::proof_of_existence(call),
+            idempotency_key: None,
+        };
+        let content = "Hello, world!".to_string();
+
+        // Bob offers less than the minimum transfer, and is frozen before alice accepts.
+        let block_1 = types::Block {
+            header: support::Header { block_number: 1 },
+            extrinsics: vec![
+                poe(&alice, proof_of_existence::Call::create_claim { claim: content.clone() }),
+                poe(&bob, proof_of_existence::Call::make_offer { claim: content.clone(), price: 5 }),
+            ],
+        };
+        runtime.execute_block(block_1).expect("invalid block");
+        runtime.balances.set_root_account(Some(alice.clone()));
+        assert_eq!(runtime.balances.freeze(alice.clone(), bob.clone()), Ok(()));
+
+        let block_2 = types::Block {
+            header: support::Header { block_number: 2 },
+            extrinsics: vec![poe(&alice, proof_of_existence::Call::accept_offer { claim: content.clone(), buyer: bob.clone() })],
+        };
+        runtime.execute_block(block_2).expect("invalid block");
+        assert_eq!(runtime.proof_of_existence.get_claim(&content), Some(&(bob.clone(), 1)));
+        assert_eq!(runtime.balances.balance(&alice), 100 - 2 - 10 + 5); // Two fees and the claim deposit.
+        assert_eq!(runtime.balances.balance(&bob), 100 - 1 - 5);
+        assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+        assert_eq!(runtime.try_state_all(), Ok(()));
+    }
+
+    #[test]
+    fn verify_claim() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        let content = "Hello, world!".to_string();
+        runtime.balances.set_balance(&alice, 100);
+
+        // Alice claims the content in block 2.
+        assert_eq!(runtime.execute_empty_blocks_until(1), Ok(()));
+        let block_2 = types::Block {
+            header: support::Header { block_number: 2 },
+            extrinsics: vec![support::Extrinsic {
+                caller: alice.clone(),
+                call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: content.clone() }),
+                idempotency_key: None,
+            }],
+        };
+        runtime.execute_block(block_2).expect("invalid block");
+
+        assert_eq!(runtime.verify_claim(&content, &alice, 1), Err(proof_of_existence::VerifyError::ClaimedTooLate));
+        assert_eq!(runtime.verify_claim(&content, &alice, 2), Ok(()));
+        assert_eq!(runtime.verify_claim(&content, &"bob".to_string(), 2), Err(proof_of_existence::VerifyError::WrongOwner));
+    }
+
+    #[test]
+    fn content_len() {
+        let mut runtime = Runtime::new().expect("invalid configuration");
+        let alice = "alice".to_string();
+        runtime.balances.set_balance(&alice, 100);
+        let create_claim = |claim| RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim });
+
+        assert_eq!(runtime.dispatch(alice.clone(), create_claim(String::new())), Err("content is empty"));
+        assert_eq!(runtime.dispatch(alice.clone(), create_claim("a".repeat(1025))), Err("content too large"));
+        assert_eq!(runtime.dispatch(alice.clone(), create_claim("a".repeat(1024))), Ok(()));
+        assert_eq!(runtime.balances.balance(&alice), 90); // Only the claim deposit, as no fee is charged.
+    }
+}
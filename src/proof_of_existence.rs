@@ -1,110 +1,1722 @@
 // The Proof of Existence Pallet uses the blockchain to provide a secure and immutable ledger that can be used
 // to verify the existence of a particular document, file, or piece of data at a specific point in time.
-use crate::support::DispatchResult;
+use crate::support::{
+	CheckConfig, ConfigSeverity, Currency, OnBlockStart, TakeEvents, Transactional, TryState,
+};
 use core::fmt::Debug;
-use std::collections::BTreeMap;
+use num::traits::Zero;
+use std::collections::{BTreeMap, BTreeSet};
 
 pub trait Config: crate::system::Config {
-	// The type which represents the content that can be claimed using this pallet.
-	// Could be the content directly as bytes, or better yet the hash of that content.
-	// We leave that decision to the runtime developer.
-	type Content: Debug + Ord;
+	// The type which represents the content that can be claimed using this pallet, such as bytes
+	// or text. Only the hash of the content is stored, see `Hasher`.
+	type Content: Debug + Clone + AsRef<[u8]> + ContentLen;
+	// Maps content to the canonical form it is claimed under, so near-duplicate content (e.g. the
+	// same text with different whitespace) collides onto one claim. Use `Identity` to keep content
+	// as it is.
+	type Normalizer: NormalizeContent<Self::Content>;
+	// Hashes the normalized content into the fixed-size key claims are stored under.
+	type Hasher: HashContent;
+	// Where the funds of accounts live, so claims can be charged for. The runtime passes it to the
+	// calls which take a `currency`, see `#[currency(...)]` on the pallet field of the runtime.
+	type Currency: Currency<Self::AccountId>;
+	// The deposit reserved from the caller for each claim, returned when the claim is revoked, so
+	// squatting on content costs something. Zero disables the deposit.
+	const CLAIM_DEPOSIT: BalanceOf<Self>;
+	// The maximum number of claims created by one `create_claims` call.
+	const MAX_BATCH_CLAIMS: usize;
+	// The maximum number of entries kept in the history of each claim. The oldest entries are
+	// dropped first.
+	const MAX_HISTORY_LEN: usize;
+	// The number of blocks a claim lasts, unless its owner renews it. Once expired, anyone can
+	// claim the content again. Zero means claims never expire.
+	const CLAIM_TTL: Self::BlockNumber;
+	// The maximum size of claimed content in bytes, so claims can not bloat the state.
+	const MAX_CONTENT_LEN: usize = 1024;
+	// The maximum length of the metadata an owner can attach to a claim.
+	const MAX_METADATA_LEN: usize;
 }
 
+// The size of content in bytes, as limited by `MAX_CONTENT_LEN`.
+pub trait ContentLen {
+	fn len_bytes(&self) -> usize;
+}
+
+impl ContentLen for &str {
+	fn len_bytes(&self) -> usize {
+		self.len()
+	}
+}
+
+impl ContentLen for String {
+	fn len_bytes(&self) -> usize {
+		self.len()
+	}
+}
+
+impl ContentLen for Vec<u8> {
+	fn len_bytes(&self) -> usize {
+		self.len()
+	}
+}
+
+impl<const N: usize> ContentLen for [u8; N] {
+	fn len_bytes(&self) -> usize {
+		N
+	}
+}
+
+// The balance type of the currency of the Proof of Existence pallet.
+pub type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as crate::system::Config>::AccountId>>::Balance;
+
+// The hash type claims of the Proof of Existence pallet are stored under.
+pub type HashOf<T> = <<T as Config>::Hasher as HashContent>::Hash;
+
+// Hashes content down to a fixed-size key.
+pub trait HashContent {
+	type Hash: Debug + Ord + Clone;
+	fn hash(content: &[u8]) -> Self::Hash;
+}
+
+// The 64-bit FNV-1a hash. It is fast and simple, but not cryptographic: someone can craft content
+// colliding with an existing claim, which a real chain would prevent with a hash like blake2.
+pub struct Fnv64;
+
+impl HashContent for Fnv64 {
+	type Hash = [u8; 8];
+
+	fn hash(content: &[u8]) -> [u8; 8] {
+		const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+		const PRIME: u64 = 0x0000_0100_0000_01b3;
+		let hash = content
+			.iter()
+			.fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(PRIME));
+		hash.to_be_bytes()
+	}
+}
+
+// Turns content into its canonical form before it is stored or looked up.
+pub trait NormalizeContent<Content> {
+	fn normalize(content: Content) -> Content;
+}
+
+// The default normalizer, which keeps content exactly as it is.
+pub struct Identity;
+
+impl<Content> NormalizeContent<Content> for Identity {
+	fn normalize(content: Content) -> Content {
+		content
+	}
+}
+
+// A normalizer for text content stored as bytes: it trims the text, collapses every run of
+// whitespace into a single space, and lowercases ASCII letters. The demo runtime claims text
+// exactly as it is, so only the tests use it.
+#[allow(dead_code)]
+pub struct TextNormalizer;
+
+impl NormalizeContent<Vec<u8>> for TextNormalizer {
+	fn normalize(content: Vec<u8>) -> Vec<u8> {
+		let words = content
+			.split(|byte| byte.is_ascii_whitespace())
+			.filter(|word| !word.is_empty())
+			.collect::<Vec<_>>();
+		words.join(&b' ').to_ascii_lowercase()
+	}
+}
+
+// The errors of the Proof of Existence pallet. At the runtime level, each error becomes its message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PoeError {
+	// Someone already has an active claim on the content.
+	AlreadyClaimed,
+	// Nobody has claimed the content.
+	ClaimNotFound,
+	// The claim belongs to another account.
+	NotOwner,
+	// The caller can not afford the claim deposit.
+	CannotAffordDeposit,
+	// The batch has more than `MAX_BATCH_CLAIMS` claims.
+	TooManyClaims,
+	// The same content is claimed twice in one batch.
+	DuplicateContent,
+	// The claim has expired, and can only be claimed again.
+	ClaimExpired,
+	// The bidder has no offer on the claim.
+	OfferNotFound,
+	// The bidder already has an offer on the claim.
+	OfferAlreadyMade,
+	// The bidder can not afford to escrow the price.
+	CannotAffordOffer,
+	// Owners can not buy their own claim.
+	OwnClaim,
+	// The escrowed price could not be paid to the owner.
+	OfferPaymentFailed,
+	// The call is reserved to the root account.
+	NotRoot,
+	// The content is longer than `MAX_CONTENT_LEN`.
+	ContentTooLarge,
+	// Empty content can not be claimed.
+	EmptyContent,
+	// The metadata is longer than `MAX_METADATA_LEN`.
+	MetadataTooLong,
+}
+
+impl PoeError {
+	// The message describing the error.
+	pub fn message(&self) -> &'static str {
+		match self {
+			PoeError::AlreadyClaimed => "this content is already claimed",
+			PoeError::ClaimNotFound => "claim does not exist",
+			PoeError::NotOwner => "this content is owned by someone else",
+			PoeError::CannotAffordDeposit => "cannot afford claim deposit",
+			PoeError::TooManyClaims => "too many claims in batch",
+			PoeError::DuplicateContent => "duplicate content in batch",
+			PoeError::ClaimExpired => "claim has expired",
+			PoeError::OfferNotFound => "offer does not exist",
+			PoeError::OfferAlreadyMade => "offer already made",
+			PoeError::CannotAffordOffer => "cannot afford offer",
+			PoeError::OwnClaim => "cannot make an offer on your own claim",
+			PoeError::OfferPaymentFailed => "offer payment failed",
+			PoeError::NotRoot => "caller is not root",
+			PoeError::ContentTooLarge => "content too large",
+			PoeError::EmptyContent => "content is empty",
+			PoeError::MetadataTooLong => "metadata too long",
+		}
+	}
+}
+
+impl core::fmt::Display for PoeError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(self.message())
+	}
+}
+
+// Lets Proof of Existence calls return their errors to the runtime, which dispatches with
+// `DispatchResult`.
+impl From<PoeError> for &'static str {
+	fn from(error: PoeError) -> Self {
+		error.message()
+	}
+}
+
+// Why a claim could not be verified, see `verify_claim`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerifyError {
+	// Nobody has an active claim on the content.
+	NotClaimed,
+	// The content is claimed by another account.
+	WrongOwner,
+	// The content was claimed after the requested block.
+	ClaimedTooLate,
+}
+
+// The owner of a claim, and the block at which it was made.
+pub type Claim<T> = (<T as crate::system::Config>::AccountId, <T as crate::system::Config>::BlockNumber);
+
+// An entry in the history of a claim: who owns the claim from a block on, or `None` once it was
+// revoked.
+pub type HistoryEntry<T> =
+	(Option<<T as crate::system::Config>::AccountId>, <T as crate::system::Config>::BlockNumber);
+
+// The events of the Proof of Existence pallet, in the order they happened.
+// Every event is about a claim, and keeps the `Claim` prefix for readability.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountId, Hash, BlockNumber, Balance> {
+	// `owner` claimed the content hashed to `claim`.
+	ClaimCreated { owner: AccountId, claim: Hash },
+	// The owner of the content hashed to `claim` changed from `from` to `to`.
+	ClaimTransferred { from: AccountId, to: AccountId, claim: Hash },
+	// `owner` revoked their claim on the content hashed to `claim`.
+	ClaimRevoked { owner: AccountId, claim: Hash },
+	// The expired claim of `owner` on the content hashed to `claim` was replaced by a new claim.
+	ClaimExpired { owner: AccountId, claim: Hash },
+	// `owner` renewed their claim on the content hashed to `claim`, which now expires at `expires_at`.
+	ClaimRenewed { owner: AccountId, claim: Hash, expires_at: BlockNumber },
+	// `owner` let `delegate` revoke or transfer their claim on the content hashed to `claim`.
+	ClaimDelegateSet { owner: AccountId, claim: Hash, delegate: AccountId },
+	// `owner` removed the delegate of their claim on the content hashed to `claim`.
+	ClaimDelegateCleared { owner: AccountId, claim: Hash },
+	// `bidder` offered `price` for the claim on the content hashed to `claim`.
+	ClaimOfferMade { bidder: AccountId, claim: Hash, price: Balance },
+	// `bidder` cancelled their offer for the claim on the content hashed to `claim`.
+	ClaimOfferCancelled { bidder: AccountId, claim: Hash },
+	// `seller` sold their claim on the content hashed to `claim` to `buyer` for `price`.
+	ClaimOfferAccepted { seller: AccountId, buyer: AccountId, claim: Hash, price: Balance },
+	// The root account removed the claim of `owner` on the content hashed to `claim`.
+	ClaimForceRevoked { owner: AccountId, claim: Hash },
+	// `owner` attached metadata to their claim on the content hashed to `claim`.
+	ClaimMetadataSet { owner: AccountId, claim: Hash },
+	// `owner` removed the metadata of their claim on the content hashed to `claim`.
+	ClaimMetadataCleared { owner: AccountId, claim: Hash },
+}
+
+// The events of the Proof of Existence pallet, for the types of its configuration.
+pub type EventOf<T> = Event<
+	<T as crate::system::Config>::AccountId,
+	HashOf<T>,
+	<T as crate::system::Config>::BlockNumber,
+	BalanceOf<T>,
+>;
+
 // This is the Proof of Existence Module.
 // It is a simple module that allows accounts to claim existence of some data.
 #[derive(Debug)]
 pub struct Pallet<T: Config> {
-	// A simple storage map from content to the owner of that content.
-	// Accounts can make multiple different claims, but each claim can only have one owner.
-	claims: BTreeMap<T::Content, T::AccountId>,
+	// A simple storage map from the hash of content to the owner of that content, and the block at
+	// which it was claimed. Accounts can make multiple different claims, but each claim can only
+	// have one owner.
+	claims: BTreeMap<HashOf<T>, (T::AccountId, T::BlockNumber)>,
+	// The current block number, as told by the runtime when a block starts.
+	block_number: T::BlockNumber,
+	// Events not yet collected by the runtime.
+	events: Vec<EventOf<T>>,
+	// The deposit reserved for each claim, with the account which paid it and gets it back. The
+	// deposit stays with its payer when the claim is transferred.
+	deposits: BTreeMap<HashOf<T>, (T::AccountId, BalanceOf<T>)>,
+	// Every change of owner of each content, oldest first, at most `MAX_HISTORY_LEN` of them.
+	// The history outlives the claim, so revoked content can still be audited.
+	history: BTreeMap<HashOf<T>, Vec<HistoryEntry<T>>>,
+	// The block at which each claim expires, unless renewed before. Claims have no expiry when
+	// `CLAIM_TTL` is zero.
+	expiries: BTreeMap<HashOf<T>, T::BlockNumber>,
+	// The account each owner allowed to revoke or transfer their claim on their behalf. A
+	// delegate is cleared when the claim is revoked or changes owner.
+	delegates: BTreeMap<HashOf<T>, T::AccountId>,
+	// The price each bidder offers for each claim, reserved from the bidder until the offer is
+	// cancelled or accepted. Offers are refunded when their claim is revoked or expires.
+	offers: BTreeMap<HashOf<T>, BTreeMap<T::AccountId, BalanceOf<T>>>,
+	// The privileged account allowed to make root-only calls, if any.
+	root_account: Option<T::AccountId>,
+	// The metadata, e.g. a title or URI, each owner attached to their claim. It describes the
+	// content, so it stays when the claim is transferred, and goes when the claim is revoked.
+	metadata: BTreeMap<HashOf<T>, Vec<u8>>,
+	// The block from which the current owner of each claim owns it. Unlike the block of the
+	// claim itself, it changes when the claim is transferred.
+	owned_since: BTreeMap<HashOf<T>, T::BlockNumber>,
 }
 
 impl<T: Config> Pallet<T> {
 	// Create a new instance of the Proof of Existence Module.
 	pub fn new() -> Self {
-		Self { claims: BTreeMap::new() }
+		Self {
+			claims: BTreeMap::new(),
+			block_number: T::BlockNumber::zero(),
+			events: Vec::new(),
+			deposits: BTreeMap::new(),
+			history: BTreeMap::new(),
+			expiries: BTreeMap::new(),
+			delegates: BTreeMap::new(),
+			offers: BTreeMap::new(),
+			root_account: None,
+			metadata: BTreeMap::new(),
+			owned_since: BTreeMap::new(),
+		}
+	}
+
+	// Get the key `content` is claimed under: the hash of its canonical form.
+	pub fn content_hash(content: T::Content) -> HashOf<T> {
+		T::Hasher::hash(T::Normalizer::normalize(content).as_ref())
+	}
+
+	// Get the owner (if any) of a claim, and the block at which it was claimed. The claim is
+	// normalized and hashed before the lookup.
+	pub fn get_claim(&self, claim: &T::Content) -> Option<&Claim<T>> {
+		self.get_claim_by_hash(&Self::content_hash(claim.clone()))
+	}
+
+	// Get the owner (if any) of the claim stored under `hash`, and the block at which it was
+	// claimed, for callers which only know the hash of the content.
+	pub fn get_claim_by_hash(&self, hash: &HashOf<T>) -> Option<&Claim<T>> {
+		self.claims.get(hash).filter(|_| !self.is_expired(hash))
+	}
+
+	// Whether someone has an active claim on `claim`.
+	pub fn is_claimed(&self, claim: &T::Content) -> bool {
+		self.get_claim(claim).is_some()
+	}
+
+	// Get the number of active claims. Expired claims are not counted.
+	pub fn claim_count(&self) -> usize {
+		self.active_claims().count()
+	}
+
+	// Get the number of active claims of each account which has any.
+	pub fn counts_by_owner(&self) -> BTreeMap<&T::AccountId, usize> {
+		let mut counts = BTreeMap::new();
+		for (owner, _) in self.active_claims() {
+			*counts.entry(owner).or_default() += 1;
+		}
+		counts
+	}
+
+	// The claims which have not expired. Counts are computed from the claims themselves rather
+	// than cached, so they can not drift from them.
+	fn active_claims(&self) -> impl Iterator<Item = &Claim<T>> {
+		self.claims.iter().filter(|(claim, _)| !self.is_expired(claim)).map(|(_, claim)| claim)
+	}
+
+	// Check that `claim` is claimed by `owner`, who has owned it since block `at_or_before` or
+	// earlier. An account which received the claim by a transfer owns it from that block on.
+	pub fn verify_claim(
+		&self,
+		claim: &T::Content,
+		owner: &T::AccountId,
+		at_or_before: T::BlockNumber,
+	) -> Result<(), VerifyError> {
+		let key = Self::content_hash(claim.clone());
+		let (claim_owner, claimed_at) = self.get_claim_by_hash(&key).ok_or(VerifyError::NotClaimed)?;
+		if claim_owner != owner {
+			return Err(VerifyError::WrongOwner);
+		}
+		let owned_since = self.owned_since.get(&key).unwrap_or(claimed_at);
+		if *owned_since > at_or_before {
+			return Err(VerifyError::ClaimedTooLate);
+		}
+		Ok(())
+	}
+
+	// Get the block at which a claim expires, if it exists and expires at all.
+	pub fn claim_expiry(&self, claim: &T::Content) -> Option<T::BlockNumber> {
+		self.expiries.get(&Self::content_hash(claim.clone())).copied()
+	}
+
+	// Find the claim for arbitrary `content`: returns the key the content is claimed under, and
+	// the owner of that claim and its block if it exists.
+	pub fn find_claim_raw(
+		&self,
+		content: T::Content,
+	) -> (HashOf<T>, Option<&Claim<T>>) {
+		let key = Self::content_hash(content);
+		let owner = self.get_claim_by_hash(&key);
+		(key, owner)
+	}
+
+	// Get every change of owner of a claim, oldest first. Each entry has the owner from that block
+	// on, or `None` once the claim was revoked. Only the last `MAX_HISTORY_LEN` changes are kept.
+	pub fn claim_history(&self, claim: &T::Content) -> &[HistoryEntry<T>] {
+		self.history
+			.get(&Self::content_hash(claim.clone()))
+			.map(Vec::as_slice)
+			.unwrap_or(&[])
+	}
+
+	// Record that `claim` is owned by `owner` from the current block on, or by nobody.
+	fn note_owner(&mut self, claim: &HashOf<T>, owner: Option<T::AccountId>) {
+		if T::MAX_HISTORY_LEN == 0 {
+			return;
+		}
+		let history = self.history.entry(claim.clone()).or_default();
+		if history.len() == T::MAX_HISTORY_LEN {
+			history.remove(0);
+		}
+		history.push((owner, self.block_number));
+	}
+
+	// Get the key `content` would be claimed under, checking that its canonical form (the bytes
+	// which are hashed) is neither empty nor too large.
+	fn claimable_hash(content: T::Content) -> Result<HashOf<T>, PoeError> {
+		let content = T::Normalizer::normalize(content);
+		match content.len_bytes() {
+			0 => Err(PoeError::EmptyContent),
+			len if len > T::MAX_CONTENT_LEN => Err(PoeError::ContentTooLarge),
+			_ => Ok(T::Hasher::hash(content.as_ref())),
+		}
+	}
+
+	// Whether `claim` exists but has expired, so it can be claimed again.
+	fn is_expired(&self, claim: &HashOf<T>) -> bool {
+		self.expiries.get(claim).is_some_and(|expires_at| *expires_at <= self.block_number)
+	}
+
+	// Whether `claim` exists and has not expired.
+	fn is_active(&self, claim: &HashOf<T>) -> bool {
+		self.claims.contains_key(claim) && !self.is_expired(claim)
+	}
+
+	// Get the metadata (if any) of a claim.
+	pub fn get_claim_metadata(&self, claim: &T::Content) -> Option<&[u8]> {
+		self.metadata.get(&Self::content_hash(claim.clone())).map(Vec::as_slice)
+	}
+
+	// Get the delegate (if any) of a claim.
+	pub fn claim_delegate(&self, claim: &T::Content) -> Option<&T::AccountId> {
+		self.delegates.get(&Self::content_hash(claim.clone()))
+	}
+
+	// Check that `caller` owns the active claim on `claim`, returning the key it is stored under.
+	fn ensure_active_owner(
+		&self,
+		caller: &T::AccountId,
+		claim: T::Content,
+	) -> Result<HashOf<T>, PoeError> {
+		let claim = Self::content_hash(claim);
+		let (owner, _) = self.claims.get(&claim).ok_or(PoeError::ClaimNotFound)?;
+		if self.is_expired(&claim) {
+			return Err(PoeError::ClaimExpired);
+		}
+		if caller != owner {
+			return Err(PoeError::NotOwner);
+		}
+		Ok(claim)
 	}
 
-    // Get the owner (if any) of a claim.
-	pub fn get_claim(&self, claim: &T::Content) -> Option<&T::AccountId> {
-		self.claims.get(&claim)
+	// Set the privileged account allowed to make root-only calls. `None` disables them.
+	pub fn set_root_account(&mut self, root: Option<T::AccountId>) {
+		self.root_account = root;
+	}
+
+	// Get the privileged account allowed to make root-only calls, if any.
+	pub fn root_account(&self) -> Option<&T::AccountId> {
+		self.root_account.as_ref()
+	}
+
+	// Check that `caller` is the root account.
+	fn ensure_root(&self, caller: &T::AccountId) -> Result<(), PoeError> {
+		if self.root_account.as_ref() != Some(caller) {
+			return Err(PoeError::NotRoot);
+		}
+		Ok(())
+	}
+
+	// Get the price `bidder` offers for a claim, if any.
+	pub fn offer(&self, claim: &T::Content, bidder: &T::AccountId) -> Option<BalanceOf<T>> {
+		self.offers.get(&Self::content_hash(claim.clone()))?.get(bidder).copied()
+	}
+
+	// Whether `caller` can revoke or transfer `claim` of `owner`: either the owner, or its delegate.
+	fn can_manage(&self, caller: &T::AccountId, owner: &T::AccountId, claim: &HashOf<T>) -> bool {
+		caller == owner || self.delegates.get(claim) == Some(caller)
+	}
+
+	// The block at which a claim made or renewed now expires, if claims expire at all.
+	fn expiry_from_now(&self) -> Option<T::BlockNumber> {
+		if T::CLAIM_TTL.is_zero() {
+			return None;
+		}
+		let mut expires_at = self.block_number;
+		expires_at += T::CLAIM_TTL;
+		Some(expires_at)
+	}
+
+	// Store a new `claim` of `owner` at the current block, whose `deposit` (if any) was reserved.
+	// An expired claim on the same content is removed first.
+	fn insert_claim(
+		&mut self,
+		owner: T::AccountId,
+		currency: &mut T::Currency,
+		claim: HashOf<T>,
+		deposit: Option<BalanceOf<T>>,
+	) {
+		if let Some(previous) = self.remove_claim(currency, &claim) {
+			self.events.push(Event::ClaimExpired { owner: previous, claim: claim.clone() });
+		}
+		if let Some(expires_at) = self.expiry_from_now() {
+			self.expiries.insert(claim.clone(), expires_at);
+		}
+		if let Some(deposit) = deposit {
+			self.deposits.insert(claim.clone(), (owner.clone(), deposit));
+		}
+		self.claims.insert(claim.clone(), (owner.clone(), self.block_number));
+		self.owned_since.insert(claim.clone(), self.block_number);
+		self.note_owner(&claim, Some(owner.clone()));
+		self.events.push(Event::ClaimCreated { owner, claim });
+	}
+
+	// Remove `claim` if it exists, returning its deposit to the account which paid it. Returns
+	// the owner of the removed claim.
+	fn remove_claim(&mut self, currency: &mut T::Currency, claim: &HashOf<T>) -> Option<T::AccountId> {
+		let (owner, _) = self.claims.remove(claim)?;
+		self.expiries.remove(claim);
+		self.delegates.remove(claim);
+		self.metadata.remove(claim);
+		self.owned_since.remove(claim);
+		if let Some((depositor, deposit)) = self.deposits.remove(claim) {
+			currency.unreserve(&depositor, deposit);
+		}
+		for (bidder, price) in self.offers.remove(claim).unwrap_or_default() {
+			currency.unreserve(&bidder, price);
+		}
+		self.note_owner(claim, None);
+		Some(owner)
+	}
+
+	// Remove the offer of `bidder` for `claim`, returning its price.
+	fn take_offer(&mut self, claim: &HashOf<T>, bidder: &T::AccountId) -> Option<BalanceOf<T>> {
+		let offers = self.offers.get_mut(claim)?;
+		let price = offers.remove(bidder)?;
+		if offers.is_empty() {
+			self.offers.remove(claim);
+		}
+		Some(price)
+	}
+
+	// Hand `claim` from its current owner `from` to `to`, dropping its delegate.
+	fn set_owner(&mut self, claim: HashOf<T>, from: T::AccountId, to: T::AccountId) {
+		if let Some((owner, _)) = self.claims.get_mut(&claim) {
+			*owner = to.clone();
+		}
+		self.delegates.remove(&claim);
+		self.owned_since.insert(claim.clone(), self.block_number);
+		self.note_owner(&claim, Some(to.clone()));
+		self.events.push(Event::ClaimTransferred { from, to, claim });
 	}
 }
 #[macros::call] // This is the call macro. 
 impl<T: Config> Pallet<T>{
 
-	// Create a new claim on behalf of the `caller`.
-	// This function will return an error if someone already has claimed that content.
-	pub fn create_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
-		if self.claims.contains_key(&claim) {
-			return Err(&"this content is already claimed");
+	/// Create a new claim on behalf of the `caller`, recording the current block number.
+	/// Only the hash of the content is stored.
+	/// The claim deposit is reserved from the caller until the claim is revoked.
+	/// This function will return an error if the content is empty or larger than
+	/// `MAX_CONTENT_LEN`, if someone already has claimed that content, or if the caller can not
+	/// afford the deposit.
+	///
+	/// - `claim`: The content being claimed.
+	pub fn create_claim(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+		claim: T::Content,
+	) -> Result<(), PoeError> {
+		let claim = Self::claimable_hash(claim)?;
+		if self.is_active(&claim) {
+			return Err(PoeError::AlreadyClaimed);
+		}
+		let deposit = (!T::CLAIM_DEPOSIT.is_zero()).then_some(T::CLAIM_DEPOSIT);
+		if let Some(deposit) = deposit {
+			currency.reserve(&caller, deposit).map_err(|_| PoeError::CannotAffordDeposit)?;
+		}
+		self.insert_claim(caller, currency, claim, deposit);
+		Ok(())
+	}
+
+	/// Create several claims at once on behalf of the `caller`, like `create_claim`. Either every
+	/// claim is created, or the call fails and none are. A deposit is reserved for each claim.
+	///
+	/// - `claims`: The contents being claimed, at most `MAX_BATCH_CLAIMS` of them.
+	pub fn create_claims(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+		claims: Vec<T::Content>,
+	) -> Result<(), PoeError> {
+		if claims.len() > T::MAX_BATCH_CLAIMS {
+			return Err(PoeError::TooManyClaims);
+		}
+		// The whole set is checked before anything is written.
+		let claims = claims.into_iter().map(Self::claimable_hash).collect::<Result<Vec<_>, _>>()?;
+		let mut seen = BTreeSet::new();
+		for claim in &claims {
+			if self.is_active(claim) {
+				return Err(PoeError::AlreadyClaimed);
+			}
+			if !seen.insert(claim) {
+				return Err(PoeError::DuplicateContent);
+			}
+		}
+
+		let deposit = (!T::CLAIM_DEPOSIT.is_zero()).then_some(T::CLAIM_DEPOSIT);
+		if let Some(deposit) = deposit {
+			for reserved in 0..claims.len() {
+				if currency.reserve(&caller, deposit).is_err() {
+					// Return the deposits reserved for the claims before.
+					for _ in 0..reserved {
+						currency.unreserve(&caller, deposit);
+					}
+					return Err(PoeError::CannotAffordDeposit);
+				}
+			}
+		}
+		for claim in claims {
+			self.insert_claim(caller.clone(), currency, claim, deposit);
+		}
+		Ok(())
+	}
+
+	/// Revoke an existing claim on some content, returning its deposit to the account which paid it.
+	/// This function should only succeed if the caller is the owner of an existing claim, or its
+	/// delegate. It will return an error if the claim does not exist, or if the caller is neither.
+	///
+	/// - `claim`: The content whose claim is revoked.
+	pub fn revoke_claim(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+		claim: T::Content,
+	) -> Result<(), PoeError> {
+		let claim = Self::content_hash(claim);
+		let (owner, _) = self.claims.get(&claim).ok_or(PoeError::ClaimNotFound)?;
+		if !self.can_manage(&caller, owner, &claim) {
+			return Err(PoeError::NotOwner);
+		}
+		let owner = owner.clone();
+		self.remove_claim(currency, &claim);
+		self.events.push(Event::ClaimRevoked { owner, claim });
+		Ok(())
+	}
+
+	/// Revoke every claim of the `caller` at once, e.g. when their key is compromised, returning
+	/// each deposit to the account which paid it. Owning no claims is not an error.
+	pub fn revoke_all(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+	) -> Result<(), PoeError> {
+		// Claims are not indexed by owner, so find them all before removing any.
+		let claims = self
+			.claims
+			.iter()
+			.filter(|(_, (owner, _))| *owner == caller)
+			.map(|(claim, _)| claim.clone())
+			.collect::<Vec<_>>();
+		for claim in claims {
+			self.remove_claim(currency, &claim);
+			self.events.push(Event::ClaimRevoked { owner: caller.clone(), claim });
+		}
+		Ok(())
+	}
+
+	/// Hand an existing claim to another account, keeping the block at which it was made.
+	/// Unlike revoking and claiming again, nobody else can claim the content in between.
+	/// This function will return an error if the claim does not exist, or if the caller is neither
+	/// the owner nor its delegate. Transferring a claim to its owner changes nothing, and raises no
+	/// event.
+	///
+	/// - `claim`: The content whose claim is transferred.
+	/// - `new_owner`: The account which owns the claim afterwards.
+	pub fn transfer_claim(
+		&mut self,
+		caller: T::AccountId,
+		claim: T::Content,
+		new_owner: T::AccountId,
+	) -> Result<(), PoeError> {
+		let claim = Self::content_hash(claim);
+		if self.is_expired(&claim) {
+			return Err(PoeError::ClaimExpired);
 		}
-		self.claims.insert(claim, caller);
+		let (owner, _) = self.claims.get(&claim).ok_or(PoeError::ClaimNotFound)?;
+		if !self.can_manage(&caller, owner, &claim) {
+			return Err(PoeError::NotOwner);
+		}
+		if *owner == new_owner {
+			return Ok(());
+		}
+		self.set_owner(claim, owner.clone(), new_owner);
 		Ok(())
 	}
 
-	// Revoke an existing claim on some content.
-	// This function should only succeed if the caller is the owner of an existing claim.
-	// It will return an error if the claim does not exist, or if the caller is not the owner.
-	pub fn revoke_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
-		let owner = self.get_claim(&claim).ok_or("claim does not exist")?;
+	/// Offer to buy a claim for `price`, which is reserved from the caller until the offer is
+	/// cancelled or accepted. Each account can have one offer per claim. This function will
+	/// return an error if the claim does not exist or has expired, if the caller owns it or
+	/// already made an offer, or if the caller can not afford the price.
+	///
+	/// - `claim`: The content whose claim the caller wants to buy.
+	/// - `price`: The amount paid to the owner if the offer is accepted.
+	pub fn make_offer(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+		claim: T::Content,
+		price: BalanceOf<T>,
+	) -> Result<(), PoeError> {
+		let claim = Self::content_hash(claim);
+		let (owner, _) = self.claims.get(&claim).ok_or(PoeError::ClaimNotFound)?;
+		if self.is_expired(&claim) {
+			return Err(PoeError::ClaimExpired);
+		}
+		if caller == *owner {
+			return Err(PoeError::OwnClaim);
+		}
+		if self.offers.get(&claim).is_some_and(|offers| offers.contains_key(&caller)) {
+			return Err(PoeError::OfferAlreadyMade);
+		}
+		currency.reserve(&caller, price).map_err(|_| PoeError::CannotAffordOffer)?;
+		self.offers.entry(claim.clone()).or_default().insert(caller.clone(), price);
+		self.events.push(Event::ClaimOfferMade { bidder: caller, claim, price });
+		Ok(())
+	}
+
+	/// Withdraw the offer of the caller for a claim, returning the reserved price.
+	/// This function will return an error if the caller has no offer for the claim.
+	///
+	/// - `claim`: The content whose claim the caller no longer wants to buy.
+	pub fn cancel_offer(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+		claim: T::Content,
+	) -> Result<(), PoeError> {
+		let claim = Self::content_hash(claim);
+		let price = self.take_offer(&claim, &caller).ok_or(PoeError::OfferNotFound)?;
+		currency.unreserve(&caller, price);
+		self.events.push(Event::ClaimOfferCancelled { bidder: caller, claim });
+		Ok(())
+	}
+
+	/// Sell a claim to an account which made an offer for it: the claim goes to the buyer, and
+	/// the reserved price to the caller. This function will return an error if the claim does not
+	/// exist or has expired, if the caller is not the owner, or if the buyer has no offer.
+	///
+	/// - `claim`: The content whose claim is sold.
+	/// - `buyer`: The account whose offer is accepted.
+	pub fn accept_offer(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+		claim: T::Content,
+		buyer: T::AccountId,
+	) -> Result<(), PoeError> {
+		let claim = self.ensure_active_owner(&caller, claim)?;
+		let price = self.take_offer(&claim, &buyer).ok_or(PoeError::OfferNotFound)?;
+		// The escrow is paid out as it is, so whatever happened to the buyer since the offer, e.g. a
+		// freeze, does not matter.
+		if currency.repatriate_reserved(&buyer, &caller, price).is_err() {
+			// Put the offer back as it was.
+			self.offers.entry(claim).or_default().insert(buyer, price);
+			return Err(PoeError::OfferPaymentFailed);
+		}
+		self.set_owner(claim.clone(), caller.clone(), buyer.clone());
+		self.events.push(Event::ClaimOfferAccepted { seller: caller, buyer, claim, price });
+		Ok(())
+	}
+
+	/// Let another account revoke or transfer a claim on behalf of its owner, replacing any
+	/// previous delegate. This function will return an error if the claim does not exist, if it
+	/// has expired, or if the caller is not the owner.
+	///
+	/// - `claim`: The content whose claim is delegated.
+	/// - `delegate`: The account which can manage the claim.
+	pub fn set_delegate(
+		&mut self,
+		caller: T::AccountId,
+		claim: T::Content,
+		delegate: T::AccountId,
+	) -> Result<(), PoeError> {
+		let claim = self.ensure_active_owner(&caller, claim)?;
+		self.delegates.insert(claim.clone(), delegate.clone());
+		self.events.push(Event::ClaimDelegateSet { owner: caller, claim, delegate });
+		Ok(())
+	}
+
+	/// Remove the delegate of a claim, if any. This function will return an error if the claim
+	/// does not exist, if it has expired, or if the caller is not the owner.
+	///
+	/// - `claim`: The content whose delegate is removed.
+	pub fn clear_delegate(&mut self, caller: T::AccountId, claim: T::Content) -> Result<(), PoeError> {
+		let claim = self.ensure_active_owner(&caller, claim)?;
+		if self.delegates.remove(&claim).is_some() {
+			self.events.push(Event::ClaimDelegateCleared { owner: caller, claim });
+		}
+		Ok(())
+	}
+
+	/// Remove claims regardless of their owner, e.g. to moderate content, returning their deposits
+	/// to the accounts which paid them. Content which is not claimed is skipped. A
+	/// `ClaimForceRevoked` event is raised for each removed claim. Only the root account can call
+	/// this function.
+	///
+	/// - `claims`: The contents whose claims are removed.
+	pub fn force_revoke_claims(
+		&mut self,
+		caller: T::AccountId,
+		currency: &mut T::Currency,
+		claims: Vec<T::Content>,
+	) -> Result<(), PoeError> {
+		self.ensure_root(&caller)?;
+		for claim in claims {
+			let claim = Self::content_hash(claim);
+			if let Some(owner) = self.remove_claim(currency, &claim) {
+				self.events.push(Event::ClaimForceRevoked { owner, claim });
+			}
+		}
+		Ok(())
+	}
+
+	/// Attach metadata, e.g. a title or URI, to a claim, replacing any previous metadata. It is
+	/// kept when the claim is transferred. This function will return an error if the claim does
+	/// not exist, if it has expired, if the caller is not the owner, or if the metadata is longer
+	/// than `MAX_METADATA_LEN`.
+	///
+	/// - `claim`: The content whose claim the metadata describes.
+	/// - `metadata`: The metadata to attach.
+	pub fn set_claim_metadata(
+		&mut self,
+		caller: T::AccountId,
+		claim: T::Content,
+		metadata: Vec<u8>,
+	) -> Result<(), PoeError> {
+		let claim = self.ensure_active_owner(&caller, claim)?;
+		if metadata.len() > T::MAX_METADATA_LEN {
+			return Err(PoeError::MetadataTooLong);
+		}
+		self.metadata.insert(claim.clone(), metadata);
+		self.events.push(Event::ClaimMetadataSet { owner: caller, claim });
+		Ok(())
+	}
+
+	/// Remove the metadata of a claim, if any. This function will return an error if the claim
+	/// does not exist, if it has expired, or if the caller is not the owner.
+	///
+	/// - `claim`: The content whose metadata is removed.
+	pub fn clear_claim_metadata(&mut self, caller: T::AccountId, claim: T::Content) -> Result<(), PoeError> {
+		let claim = self.ensure_active_owner(&caller, claim)?;
+		if self.metadata.remove(&claim).is_some() {
+			self.events.push(Event::ClaimMetadataCleared { owner: caller, claim });
+		}
+		Ok(())
+	}
+
+	/// Renew an existing claim, so it expires `CLAIM_TTL` blocks from now rather than at its
+	/// current expiry. This function will return an error if the claim does not exist, if it has
+	/// already expired, or if the caller is not the owner.
+	///
+	/// - `claim`: The content whose claim is renewed.
+	pub fn renew_claim(&mut self, caller: T::AccountId, claim: T::Content) -> Result<(), PoeError> {
+		let claim = Self::content_hash(claim);
+		let (owner, _) = self.claims.get(&claim).ok_or(PoeError::ClaimNotFound)?;
+		if self.is_expired(&claim) {
+			return Err(PoeError::ClaimExpired);
+		}
 		if caller != *owner {
-			return Err(&"this content is owned by someone else");
+			return Err(PoeError::NotOwner);
+		}
+		// Claims which never expire have nothing to renew.
+		if let Some(expires_at) = self.expiry_from_now() {
+			self.expiries.insert(claim.clone(), expires_at);
+			self.events.push(Event::ClaimRenewed { owner: caller, claim, expires_at });
+		}
+		Ok(())
+	}
+}
+
+// A default pallet is an empty one, as created by `new`.
+impl<T: Config> Default for Pallet<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// The runtime collects the events of the Proof of Existence pallet after each extrinsic.
+impl<T: Config> TakeEvents for Pallet<T> {
+	type Event = EventOf<T>;
+
+	fn take_events(&mut self) -> Vec<Self::Event> {
+		core::mem::take(&mut self.events)
+	}
+}
+
+// The Proof of Existence pallet keeps track of the block number, which claims record.
+impl<T: Config> OnBlockStart<T::BlockNumber, T::AccountId> for Pallet<T> {
+	fn on_block_start(&mut self, block_number: T::BlockNumber, _author: Option<&T::AccountId>) {
+		self.block_number = block_number;
+	}
+}
+
+// The calls of the Proof of Existence pallet check everything before writing, so there is nothing
+// to undo.
+impl<T: Config> Transactional for Pallet<T> {
+	fn start_transaction(&mut self) {}
+	fn commit_transaction(&mut self) {}
+	fn rollback_transaction(&mut self) {}
+}
+
+// The integrity checks of the Proof of Existence pallet.
+impl<T: Config> TryState<T::BlockNumber> for Pallet<T> {
+	fn try_state(&self, _block_number: T::BlockNumber) -> Result<(), &'static str> {
+		// Deposits are returned when their claim is revoked.
+		if self.deposits.keys().any(|claim| !self.claims.contains_key(claim)) {
+			return Err("deposit stored for a missing claim");
+		}
+		if self.expiries.keys().any(|claim| !self.claims.contains_key(claim)) {
+			return Err("expiry stored for a missing claim");
+		}
+		if self.delegates.keys().any(|claim| !self.claims.contains_key(claim)) {
+			return Err("delegate stored for a missing claim");
+		}
+		if self.offers.keys().any(|claim| !self.claims.contains_key(claim)) {
+			return Err("offer stored for a missing claim");
+		}
+		if self.offers.values().any(BTreeMap::is_empty) {
+			return Err("empty offers stored for a claim");
+		}
+		if self.metadata.keys().any(|claim| !self.claims.contains_key(claim)) {
+			return Err("metadata stored for a missing claim");
+		}
+		if self.owned_since.keys().any(|claim| !self.claims.contains_key(claim)) {
+			return Err("ownership start stored for a missing claim");
 		}
-		self.claims.remove(&claim);
 		Ok(())
 	}
 }
 
+// The configuration checks of the Proof of Existence pallet.
+impl<T: Config> CheckConfig for Pallet<T> {
+	fn check_config() -> Vec<(ConfigSeverity, &'static str)> {
+		// The content type, its normalizer and its hasher can not be inconsistent, but the limits can.
+		let mut warnings = vec![];
+		if T::MAX_CONTENT_LEN == 0 {
+			warnings.push((ConfigSeverity::Error, "content length limit is zero, nothing can be claimed"));
+		}
+		if T::MAX_BATCH_CLAIMS == 0 {
+			warnings.push((ConfigSeverity::Error, "batch limit is zero, `create_claims` always fails"));
+		}
+		if T::CLAIM_TTL.is_zero() {
+			warnings.push((ConfigSeverity::Warning, "claim TTL is zero, claims never expire"));
+		}
+		if T::MAX_HISTORY_LEN == 0 {
+			warnings.push((ConfigSeverity::Warning, "history limit is zero, no claim history is kept"));
+		}
+		warnings
+	}
+}
 
 // This module defines tests for the Proof of Existence pallet.
 #[cfg(test)]
 mod test {
-    // Configuration for the tests using `TestConfig` which implements necessary traits.
-    struct TestConfig;
+    use super::{Event, HashContent, PoeError, VerifyError};
+    use crate::support::{
+        CheckConfig, ConfigSeverity, Currency, DispatchResult, OnBlockStart, TakeEvents, TryState,
+    };
+    use core::fmt::Debug;
+    use core::marker::PhantomData;
+    use std::collections::BTreeMap;
 
-    // Implement the `Config` trait for `TestConfig` to specify types for testing.
-    impl super::Config for TestConfig {
-        type Content = &'static str;  // Use static string slices for the content type.
+    // A currency which only tracks free and reserved balances, standing in for the balances pallet.
+    #[derive(Default)]
+    struct MockCurrency {
+        balances: BTreeMap<&'static str, u32>,
+        reserved: BTreeMap<&'static str, u32>,
     }
 
-    // Implement the `system::Config` for `TestConfig` to specify additional system types.
-    impl crate::system::Config for TestConfig {
-        type AccountId = &'static str;  // Use static string slices for account IDs.
-        type BlockNumber = u32;         // Define BlockNumber as an unsigned 32-bit integer.
-        type Nonce = u32;               // Define Nonce as an unsigned 32-bit integer.
+    impl Currency<&'static str> for MockCurrency {
+        type Balance = u32;
+
+        fn free_balance(&self, who: &&'static str) -> u32 {
+            *self.balances.get(who).unwrap_or(&0)
+        }
+
+        fn transfer(&mut self, from: &&'static str, to: &&'static str, amount: u32) -> DispatchResult {
+            self.withdraw(from, amount)?;
+            self.deposit(to, amount)
+        }
+
+        fn withdraw(&mut self, who: &&'static str, amount: u32) -> DispatchResult {
+            let balance = self.free_balance(who).checked_sub(amount).ok_or("Not enough funds.")?;
+            self.balances.insert(who, balance);
+            Ok(())
+        }
+
+        fn deposit(&mut self, who: &&'static str, amount: u32) -> DispatchResult {
+            let balance = self.free_balance(who).checked_add(amount).ok_or("Overflow")?;
+            self.balances.insert(who, balance);
+            Ok(())
+        }
+
+        fn reserve(&mut self, who: &&'static str, amount: u32) -> DispatchResult {
+            self.withdraw(who, amount)?;
+            *self.reserved.entry(who).or_default() += amount;
+            Ok(())
+        }
+
+        fn unreserve(&mut self, who: &&'static str, amount: u32) -> u32 {
+            let reserved = self.reserved.entry(who).or_default();
+            let actual = amount.min(*reserved);
+            *reserved -= actual;
+            *self.balances.entry(who).or_default() += actual;
+            actual
+        }
+
+        fn repatriate_reserved(
+            &mut self,
+            from: &&'static str,
+            to: &&'static str,
+            amount: u32,
+        ) -> DispatchResult {
+            let reserved = self.reserved.entry(from).or_default();
+            *reserved = reserved.checked_sub(amount).ok_or("Not enough reserved funds")?;
+            self.deposit(to, amount)
+        }
     }
 
+    // The config of every test: the content type and normalizer are picked per test config, and
+    // the claim deposit, claim TTL and batch limit can be overridden, while the rest is shared.
+    struct BaseConfig<
+        Content,
+        Normalizer,
+        const CLAIM_DEPOSIT: u32 = 0,
+        const CLAIM_TTL: u32 = 0,
+        const MAX_BATCH_CLAIMS: usize = 3,
+    >(PhantomData<(Content, Normalizer)>);
+
+    impl<
+        Content,
+        Normalizer,
+        const CLAIM_DEPOSIT: u32,
+        const CLAIM_TTL: u32,
+        const MAX_BATCH_CLAIMS: usize,
+    > super::Config for BaseConfig<Content, Normalizer, CLAIM_DEPOSIT, CLAIM_TTL, MAX_BATCH_CLAIMS>
+    where
+        Content: Debug + Clone + AsRef<[u8]> + super::ContentLen,
+        Normalizer: super::NormalizeContent<Content>,
+    {
+        type Content = Content;
+        type Normalizer = Normalizer;
+        type Hasher = super::Fnv64;
+        type Currency = MockCurrency;
+        const CLAIM_DEPOSIT: u32 = CLAIM_DEPOSIT;
+        const MAX_BATCH_CLAIMS: usize = MAX_BATCH_CLAIMS;
+        const MAX_HISTORY_LEN: usize = 4;
+        const CLAIM_TTL: u32 = CLAIM_TTL;
+        const MAX_METADATA_LEN: usize = 8;
+    }
+
+    impl<
+        Content,
+        Normalizer,
+        const CLAIM_DEPOSIT: u32,
+        const CLAIM_TTL: u32,
+        const MAX_BATCH_CLAIMS: usize,
+    > crate::system::Config
+        for BaseConfig<Content, Normalizer, CLAIM_DEPOSIT, CLAIM_TTL, MAX_BATCH_CLAIMS>
+    {
+        type AccountId = &'static str; // Use static string slices for account IDs.
+        type BlockNumber = u32;
+        type Nonce = u32;
+        const IDEMPOTENCY_WINDOW: u32 = 0; // Idempotency keys are not used in these tests.
+        type RuntimeEvent = ();
+    }
+
+    // Claims of string slices, kept exactly as they are, which are free and never expire.
+    type TestConfig = BaseConfig<&'static str, super::Identity>;
+
     // Define a test case for basic proof of existence functionality.
     #[test]
     fn basic_proof_of_existence() {
         // Create a new instance of the Pallet with the test configuration.
-        let mut poe = super::Pallet::<TestConfig>::new();
+        let mut poe = super::Pallet::<TestConfig>::default();
+        let mut currency = MockCurrency::default();
 
         // Verify that initially there is no claim for "Hello, world!".
         assert_eq!(poe.get_claim(&"Hello, world!"), None);
 
         // Create a claim for "Hello, world!" by "alice" and verify it succeeds.
-        assert_eq!(poe.create_claim(&"alice", &"Hello, world!"), Ok(()));
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
 
         // Verify that "alice" is now the owner of the "Hello, world!" claim.
-        assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"alice"));
+        assert_eq!(poe.get_claim(&"Hello, world!"), Some(&("alice", 0)));
 
         // Attempt to create another claim for "Hello, world!" by "bob" and check for failure
         // because it is already claimed by "alice".
         assert_eq!(
-            poe.create_claim(&"bob", &"Hello, world!"),
-            Err("this content is already claimed")
+            poe.create_claim("bob", &mut currency, "Hello, world!"),
+            Err(PoeError::AlreadyClaimed)
         );
 
         // Revoke "alice"'s claim on "Hello, world!" and verify it succeeds.
-        assert_eq!(poe.revoke_claim(&"alice", &"Hello, world!"), Ok(()));
+        assert_eq!(poe.revoke_claim("alice", &mut currency, "Hello, world!"), Ok(()));
 
-        // Verify that "bob" can now claim "Hello, world!" successfully.
-        assert_eq!(poe.create_claim(&"bob", &"Hello, world!"), Ok(()));
+        // Verify that "bob" can now claim "Hello, world!" successfully, at a later block.
+        poe.on_block_start(3, None);
+        assert_eq!(poe.create_claim("bob", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(poe.get_claim(&"Hello, world!"), Some(&("bob", 3)));
     }
-}
 
+    // Configuration for tests with text content, where whitespace and case variants collide.
+    type TextConfig = BaseConfig<Vec<u8>, super::TextNormalizer>;
+
+    // Define a test case for near-duplicate content colliding onto one claim.
+    #[test]
+    fn normalized_claims() {
+        let mut poe = super::Pallet::<TextConfig>::default();
+        let mut currency = MockCurrency::default();
+        let text = b"Hello, world!".to_vec();
+        let variant = b"  hello,\n\tWORLD!  ".to_vec();
+
+        // Alice claims the text, and the whitespace variant maps onto her claim.
+        assert_eq!(poe.create_claim("alice", &mut currency, text.clone()), Ok(()));
+        assert_eq!(
+            poe.create_claim("bob", &mut currency, variant.clone()),
+            Err(PoeError::AlreadyClaimed)
+        );
+        assert_eq!(poe.get_claim(&variant), Some(&("alice", 0)));
+        assert_eq!(
+            poe.find_claim_raw(variant.clone()),
+            (super::Fnv64::hash(b"hello, world!"), Some(&("alice", 0)))
+        );
+        assert_eq!(poe.try_state(0), Ok(()));
+
+        // The claim can be revoked through either form.
+        assert_eq!(poe.revoke_claim("alice", &mut currency, variant.clone()), Ok(()));
+        assert_eq!(poe.find_claim_raw(text.clone()), (super::Fnv64::hash(b"hello, world!"), None));
+        assert_eq!(poe.create_claim("bob", &mut currency, variant), Ok(()));
+        assert_eq!(poe.revoke_claim("bob", &mut currency, text), Ok(()));
+    }
+
+    // The identity normalizer keeps content byte-for-byte.
+    #[test]
+    fn identity_normalizer() {
+        let mut poe = super::Pallet::<TestConfig>::default();
+        let mut currency = MockCurrency::default();
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(poe.create_claim("bob", &mut currency, "hello,  world!"), Ok(())); // A different claim.
+        assert_eq!(
+            poe.find_claim_raw(" Hello, world!"),
+            (super::Fnv64::hash(b" Hello, world!"), None)
+        );
+    }
+
+    // Handing a claim over through revoke and claim lets someone else snipe the content.
+    #[test]
+    fn transfer_claim() {
+        let mut poe = super::Pallet::<TestConfig>::default();
+        let mut currency = MockCurrency::default();
+        poe.on_block_start(1, None);
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+
+        // Alice revokes her claim to hand it to bob, but charlie claims it first.
+        assert_eq!(poe.revoke_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(poe.create_claim("charlie", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(
+            poe.create_claim("bob", &mut currency, "Hello, world!"),
+            Err(PoeError::AlreadyClaimed)
+        );
+
+        // Transferring the claim hands it over directly, keeping the block it was made at.
+        poe.on_block_start(2, None);
+        poe.take_events();
+        assert_eq!(poe.transfer_claim("charlie", "Hello, world!", "bob"), Ok(()));
+        assert_eq!(poe.get_claim(&"Hello, world!"), Some(&("bob", 1)));
+        assert_eq!(
+            poe.transfer_claim("charlie", "Hello, world!", "charlie"),
+            Err(PoeError::NotOwner)
+        );
+        assert_eq!(
+            poe.transfer_claim("bob", "Goodbye, world!", "alice"),
+            Err(PoeError::ClaimNotFound)
+        );
+
+        // Transferring to yourself changes nothing.
+        assert_eq!(poe.transfer_claim("bob", "Hello, world!", "bob"), Ok(()));
+        assert_eq!(poe.get_claim(&"Hello, world!"), Some(&("bob", 1)));
+        assert_eq!(
+            poe.take_events(),
+            vec![Event::ClaimTransferred {
+                from: "charlie",
+                to: "bob",
+                claim: super::Fnv64::hash(b"Hello, world!"),
+            }]
+        );
+    }
+
+    // Configuration for tests where every claim takes a deposit of 10, and expires 5 blocks after
+    // it is made or renewed.
+    type DepositConfig = BaseConfig<&'static str, super::Identity, 10, 5>;
+
+    #[test]
+    fn claim_deposit() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 25);
+        currency.balances.insert("bob", 9);
+
+        // The deposit is reserved while the claim exists.
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(currency.free_balance(&"alice"), 15);
+        assert_eq!(currency.reserved.get("alice"), Some(&10));
+
+        // Bob can not afford the deposit, and nothing is taken from him.
+        assert_eq!(
+            poe.create_claim("bob", &mut currency, "Goodbye, world!"),
+            Err(PoeError::CannotAffordDeposit)
+        );
+        assert_eq!(currency.free_balance(&"bob"), 9);
+        assert_eq!(poe.get_claim(&"Goodbye, world!"), None);
+
+        // Revoking returns the exact deposit.
+        assert_eq!(poe.revoke_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(currency.free_balance(&"alice"), 25);
+        assert_eq!(currency.reserved.get("alice"), Some(&0));
+        assert_eq!(poe.try_state(0), Ok(()));
+
+        // A transferred claim returns the deposit to the account which paid it.
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(poe.transfer_claim("alice", "Hello, world!", "bob"), Ok(()));
+        assert_eq!(poe.revoke_claim("bob", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(currency.free_balance(&"alice"), 25);
+        assert_eq!(currency.free_balance(&"bob"), 9);
+    }
+
+    #[test]
+    fn create_claims() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        currency.balances.insert("bob", 25);
+        assert_eq!(poe.create_claim("bob", &mut currency, "taken"), Ok(()));
+        poe.take_events();
+
+        assert_eq!(
+            poe.create_claims("alice", &mut currency, vec!["a", "b", "a"]),
+            Err(PoeError::DuplicateContent)
+        );
+        assert_eq!(
+            poe.create_claims("alice", &mut currency, vec!["a", "taken"]),
+            Err(PoeError::AlreadyClaimed)
+        );
+        assert_eq!(
+            poe.create_claims("alice", &mut currency, vec!["a", "b", "c", "d"]),
+            Err(PoeError::TooManyClaims)
+        );
+        // Bob can afford one more deposit, but not two.
+        assert_eq!(
+            poe.create_claims("bob", &mut currency, vec!["a", "b"]),
+            Err(PoeError::CannotAffordDeposit)
+        );
+        assert_eq!(currency.free_balance(&"bob"), 15);
+        assert_eq!(currency.free_balance(&"alice"), 100);
+        assert_eq!(poe.get_claim(&"a"), None);
+        assert_eq!(poe.take_events(), vec![]);
+
+        assert_eq!(poe.create_claims("alice", &mut currency, vec!["a", "b", "c"]), Ok(()));
+        assert_eq!(poe.get_claim(&"b"), Some(&("alice", 0)));
+        assert_eq!(currency.free_balance(&"alice"), 70);
+        assert_eq!(
+            poe.take_events(),
+            vec![
+                Event::ClaimCreated { owner: "alice", claim: super::Fnv64::hash(b"a") },
+                Event::ClaimCreated { owner: "alice", claim: super::Fnv64::hash(b"b") },
+                Event::ClaimCreated { owner: "alice", claim: super::Fnv64::hash(b"c") },
+            ]
+        );
+        assert_eq!(poe.try_state(0), Ok(()));
+    }
+
+    // The history of the claim from the demo in `main.rs`.
+    #[test]
+    fn claim_history() {
+        let mut poe = super::Pallet::<TestConfig>::default();
+        let mut currency = MockCurrency::default();
+        assert_eq!(poe.claim_history(&"Hello, world!"), &[]);
+
+        poe.on_block_start(2, None);
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(
+            poe.create_claim("bob", &mut currency, "Hello, world!"),
+            Err(PoeError::AlreadyClaimed)
+        );
+        poe.on_block_start(3, None);
+        assert_eq!(poe.revoke_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(poe.create_claim("bob", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(
+            poe.claim_history(&"Hello, world!"),
+            &[(Some("alice"), 2), (None, 3), (Some("bob"), 3)]
+        );
+
+        // Only the last `MAX_HISTORY_LEN` changes are kept.
+        poe.on_block_start(4, None);
+        assert_eq!(poe.transfer_claim("bob", "Hello, world!", "charlie"), Ok(()));
+        assert_eq!(poe.transfer_claim("charlie", "Hello, world!", "dave"), Ok(()));
+        assert_eq!(
+            poe.claim_history(&"Hello, world!"),
+            &[(None, 3), (Some("bob"), 3), (Some("charlie"), 4), (Some("dave"), 4)]
+        );
+    }
+
+    // Claims are stored under the hash of their content, and can be looked up by either.
+    #[test]
+    fn content_hash() {
+        // The FNV-1a test vectors.
+        assert_eq!(super::Fnv64::hash(b""), 0xcbf2_9ce4_8422_2325u64.to_be_bytes());
+        assert_eq!(super::Fnv64::hash(b"a"), 0xaf63_dc4c_8601_ec8cu64.to_be_bytes());
+
+        let mut poe = super::Pallet::<TestConfig>::default();
+        let mut currency = MockCurrency::default();
+        let hello = super::Pallet::<TestConfig>::content_hash("Hello, world!");
+        let goodbye = super::Pallet::<TestConfig>::content_hash("Goodbye, world!");
+        assert_ne!(hello, goodbye);
+
+        // Different content makes different claims, the same content collides.
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(poe.create_claim("bob", &mut currency, "Goodbye, world!"), Ok(()));
+        assert_eq!(
+            poe.create_claim("bob", &mut currency, "Hello, world!"),
+            Err(PoeError::AlreadyClaimed)
+        );
+        assert_eq!(poe.get_claim_by_hash(&hello), Some(&("alice", 0)));
+        assert_eq!(poe.get_claim_by_hash(&goodbye), poe.get_claim(&"Goodbye, world!"));
+        assert_eq!(poe.get_claim_by_hash(&super::Fnv64::hash(b"Hello")), None);
+    }
+
+    #[test]
+    fn revoke_all() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        currency.balances.insert("bob", 100);
+        assert_eq!(poe.create_claims("alice", &mut currency, vec!["a", "b", "c"]), Ok(()));
+        assert_eq!(poe.create_claim("bob", &mut currency, "d"), Ok(()));
+        poe.take_events();
+
+        // Charlie owns nothing, which is fine.
+        assert_eq!(poe.revoke_all("charlie", &mut currency), Ok(()));
+        assert_eq!(poe.take_events(), vec![]);
+
+        // Every claim of alice is revoked and refunded, and bob keeps his.
+        assert_eq!(poe.revoke_all("alice", &mut currency), Ok(()));
+        assert_eq!(currency.free_balance(&"alice"), 100);
+        assert_eq!(poe.get_claim(&"a"), None);
+        assert_eq!(poe.get_claim(&"c"), None);
+        assert_eq!(poe.get_claim(&"d"), Some(&("bob", 0)));
+        assert_eq!(currency.free_balance(&"bob"), 90);
+        let events = poe.take_events();
+        assert_eq!(events.len(), 3);
+        for claim in ["a", "b", "c"] {
+            let claim = super::Fnv64::hash(claim.as_bytes());
+            assert!(events.contains(&Event::ClaimRevoked { owner: "alice", claim }));
+        }
+        assert_eq!(poe.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn renew_claim() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        currency.balances.insert("bob", 100);
+        let b = super::Fnv64::hash(b"b");
+
+        // A renewed claim survives past its original expiry.
+        poe.on_block_start(1, None);
+        assert_eq!(poe.create_claim("alice", &mut currency, "a"), Ok(()));
+        assert_eq!(poe.claim_expiry(&"a"), Some(6));
+        poe.on_block_start(5, None);
+        assert_eq!(poe.renew_claim("bob", "a"), Err(PoeError::NotOwner));
+        assert_eq!(poe.renew_claim("alice", "x"), Err(PoeError::ClaimNotFound));
+        assert_eq!(poe.renew_claim("alice", "a"), Ok(()));
+        assert_eq!(poe.claim_expiry(&"a"), Some(10));
+        poe.on_block_start(7, None);
+        assert_eq!(poe.get_claim(&"a"), Some(&("alice", 1)));
+
+        // Renewing one block too late fails, and anyone can claim the content again.
+        assert_eq!(poe.create_claim("alice", &mut currency, "b"), Ok(()));
+        poe.on_block_start(12, None);
+        assert_eq!(poe.renew_claim("alice", "b"), Err(PoeError::ClaimExpired));
+        assert_eq!(poe.transfer_claim("alice", "b", "bob"), Err(PoeError::ClaimExpired));
+        assert_eq!(poe.get_claim(&"b"), None);
+        poe.take_events();
+        assert_eq!(poe.create_claim("bob", &mut currency, "b"), Ok(()));
+        assert_eq!(poe.get_claim(&"b"), Some(&("bob", 12)));
+        assert_eq!(
+            poe.take_events(),
+            vec![
+                Event::ClaimExpired { owner: "alice", claim: b },
+                Event::ClaimCreated { owner: "bob", claim: b },
+            ]
+        );
+
+        // The deposit of the expired claim went back to alice.
+        assert_eq!(currency.free_balance(&"alice"), 90);
+        assert_eq!(currency.free_balance(&"bob"), 90);
+        assert_eq!(poe.try_state(12), Ok(()));
+    }
+
+    // Owned content built at runtime can be claimed, and looked up by equal content.
+    #[test]
+    fn owned_content() {
+        let mut poe = super::Pallet::<TextConfig>::default();
+        let mut currency = MockCurrency::default();
+        let content = format!("document #{}", 42).into_bytes();
+        assert_eq!(poe.create_claim("alice", &mut currency, content), Ok(()));
+        assert_eq!(poe.get_claim(&b"document #42".to_vec()), Some(&("alice", 0)));
+        assert_eq!(poe.get_claim(&b"document #43".to_vec()), None);
+    }
+
+    #[test]
+    fn delegates() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        assert_eq!(poe.create_claims("alice", &mut currency, vec!["a", "b"]), Ok(()));
+
+        // Only the owner can name a delegate.
+        assert_eq!(poe.set_delegate("custodian", "a", "custodian"), Err(PoeError::NotOwner));
+        assert_eq!(poe.set_delegate("alice", "x", "custodian"), Err(PoeError::ClaimNotFound));
+        assert_eq!(poe.set_delegate("alice", "a", "custodian"), Ok(()));
+        assert_eq!(poe.set_delegate("alice", "b", "custodian"), Ok(()));
+        assert_eq!(poe.claim_delegate(&"a"), Some(&"custodian"));
+
+        // The delegate revokes on behalf of alice, who gets her deposit back, but others can not.
+        poe.take_events();
+        assert_eq!(poe.revoke_claim("mallory", &mut currency, "a"), Err(PoeError::NotOwner));
+        assert_eq!(poe.revoke_claim("custodian", &mut currency, "a"), Ok(()));
+        assert_eq!(poe.get_claim(&"a"), None);
+        assert_eq!(currency.free_balance(&"alice"), 90);
+        assert_eq!(
+            poe.take_events(),
+            vec![Event::ClaimRevoked { owner: "alice", claim: super::Fnv64::hash(b"a") }]
+        );
+
+        // The delegate transfers the other claim, and can not touch it afterwards.
+        assert_eq!(poe.transfer_claim("custodian", "b", "bob"), Ok(()));
+        assert_eq!(poe.get_claim(&"b"), Some(&("bob", 0)));
+        assert_eq!(poe.claim_delegate(&"b"), None);
+        assert_eq!(poe.transfer_claim("custodian", "b", "custodian"), Err(PoeError::NotOwner));
+        assert_eq!(poe.revoke_claim("custodian", &mut currency, "b"), Err(PoeError::NotOwner));
+
+        // A cleared delegate can not manage the claim either.
+        assert_eq!(poe.set_delegate("bob", "b", "custodian"), Ok(()));
+        assert_eq!(poe.clear_delegate("custodian", "b"), Err(PoeError::NotOwner));
+        assert_eq!(poe.clear_delegate("bob", "b"), Ok(()));
+        assert_eq!(poe.revoke_claim("custodian", &mut currency, "b"), Err(PoeError::NotOwner));
+        assert_eq!(poe.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn claim_counts() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        currency.balances.insert("bob", 100);
+        assert_eq!(poe.claim_count(), 0);
+        assert!(poe.counts_by_owner().is_empty());
+
+        assert_eq!(poe.create_claims("alice", &mut currency, vec!["a", "b", "c"]), Ok(()));
+        assert_eq!(poe.create_claim("bob", &mut currency, "d"), Ok(()));
+        assert!(poe.is_claimed(&"a"));
+        assert!(!poe.is_claimed(&"e"));
+        assert_eq!(poe.claim_count(), 4);
+        assert_eq!(poe.counts_by_owner(), BTreeMap::from([(&"alice", 3), (&"bob", 1)]));
+
+        // Counts follow revocations and transfers.
+        assert_eq!(poe.revoke_claim("alice", &mut currency, "a"), Ok(()));
+        assert_eq!(poe.transfer_claim("alice", "b", "bob"), Ok(()));
+        assert!(!poe.is_claimed(&"a"));
+        assert_eq!(poe.claim_count(), 3);
+        assert_eq!(poe.counts_by_owner(), BTreeMap::from([(&"alice", 1), (&"bob", 2)]));
+
+        // Expired claims are not counted.
+        poe.on_block_start(2, None);
+        assert_eq!(poe.create_claim("alice", &mut currency, "e"), Ok(()));
+        poe.on_block_start(5, None);
+        assert!(!poe.is_claimed(&"c"));
+        assert_eq!(poe.claim_count(), 1);
+        assert_eq!(poe.counts_by_owner(), BTreeMap::from([(&"alice", 1)]));
+    }
+
+    #[test]
+    fn claim_offers() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        currency.balances.insert("bob", 100);
+        currency.balances.insert("carol", 20);
+        assert_eq!(poe.create_claim("alice", &mut currency, "a"), Ok(()));
+
+        // Offers are escrowed, one per bidder, and only on existing claims of others.
+        assert_eq!(poe.make_offer("bob", &mut currency, "x", 40), Err(PoeError::ClaimNotFound));
+        assert_eq!(poe.make_offer("alice", &mut currency, "a", 40), Err(PoeError::OwnClaim));
+        assert_eq!(
+            poe.make_offer("carol", &mut currency, "a", 30),
+            Err(PoeError::CannotAffordOffer)
+        );
+        assert!(poe.offers.is_empty()); // A failed offer leaves nothing behind.
+        assert_eq!(poe.try_state(0), Ok(()));
+        assert_eq!(poe.make_offer("bob", &mut currency, "a", 40), Ok(()));
+        assert_eq!(poe.make_offer("bob", &mut currency, "a", 50), Err(PoeError::OfferAlreadyMade));
+        assert_eq!(poe.make_offer("carol", &mut currency, "a", 5), Ok(()));
+        assert_eq!(currency.free_balance(&"bob"), 60);
+        assert_eq!(poe.offer(&"a", &"bob"), Some(40));
+
+        // Only the owner accepts, and only existing offers.
+        assert_eq!(poe.accept_offer("bob", &mut currency, "a", "bob"), Err(PoeError::NotOwner));
+        assert_eq!(
+            poe.accept_offer("alice", &mut currency, "a", "dave"),
+            Err(PoeError::OfferNotFound)
+        );
+        poe.take_events();
+        assert_eq!(poe.accept_offer("alice", &mut currency, "a", "bob"), Ok(()));
+        let a = super::Fnv64::hash(b"a");
+        assert_eq!(
+            poe.take_events(),
+            vec![
+                Event::ClaimTransferred { from: "alice", to: "bob", claim: a },
+                Event::ClaimOfferAccepted { seller: "alice", buyer: "bob", claim: a, price: 40 },
+            ]
+        );
+        assert_eq!(poe.get_claim(&"a"), Some(&("bob", 0)));
+        assert_eq!(poe.offer(&"a", &"bob"), None);
+        assert_eq!(currency.free_balance(&"alice"), 130);
+        assert_eq!(currency.free_balance(&"bob"), 60);
+        assert_eq!(currency.reserved.get("bob"), Some(&0));
+
+        // Cancelling refunds the offer.
+        assert_eq!(poe.cancel_offer("carol", &mut currency, "a"), Ok(()));
+        assert_eq!(poe.cancel_offer("carol", &mut currency, "a"), Err(PoeError::OfferNotFound));
+        assert_eq!(currency.free_balance(&"carol"), 20);
+
+        // Revoking the claim refunds the offers left on it.
+        assert_eq!(poe.make_offer("carol", &mut currency, "a", 5), Ok(()));
+        assert_eq!(poe.revoke_claim("bob", &mut currency, "a"), Ok(()));
+        assert_eq!(currency.free_balance(&"carol"), 20);
+        assert_eq!(currency.free_balance(&"alice"), 140); // Her claim deposit.
+        assert_eq!(poe.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn force_revoke_claims() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        currency.balances.insert("bob", 100);
+        assert_eq!(poe.create_claims("alice", &mut currency, vec!["a", "b"]), Ok(()));
+        assert_eq!(poe.create_claim("bob", &mut currency, "c"), Ok(()));
+        poe.take_events();
 
+        // Only root can force a revocation.
+        let claims = vec!["a", "x", "c", "a"];
+        assert_eq!(
+            poe.force_revoke_claims("root", &mut currency, claims.clone()),
+            Err(PoeError::NotRoot)
+        );
+        poe.set_root_account(Some("root"));
+        assert_eq!(
+            poe.force_revoke_claims("alice", &mut currency, claims.clone()),
+            Err(PoeError::NotRoot)
+        );
+
+        // Missing claims are skipped, and each removal is reported.
+        assert_eq!(poe.force_revoke_claims("root", &mut currency, claims), Ok(()));
+        assert_eq!(
+            poe.take_events(),
+            vec![
+                Event::ClaimForceRevoked { owner: "alice", claim: super::Fnv64::hash(b"a") },
+                Event::ClaimForceRevoked { owner: "bob", claim: super::Fnv64::hash(b"c") },
+            ]
+        );
+        assert_eq!(poe.claim_count(), 1);
+        assert_eq!(currency.free_balance(&"alice"), 90);
+        assert_eq!(currency.free_balance(&"bob"), 100);
+
+        // The owners can claim the content again.
+        assert_eq!(poe.create_claim("alice", &mut currency, "a"), Ok(()));
+        assert_eq!(poe.create_claim("bob", &mut currency, "c"), Ok(()));
+        assert_eq!(poe.try_state(0), Ok(()));
+    }
+
+    #[test]
+    fn verify_claim() {
+        let mut poe = super::Pallet::<TestConfig>::default();
+        let mut currency = MockCurrency::default();
+        assert_eq!(poe.verify_claim(&"Hello, world!", &"alice", 5), Err(VerifyError::NotClaimed));
+
+        poe.on_block_start(2, None);
+        assert_eq!(poe.create_claim("alice", &mut currency, "Hello, world!"), Ok(()));
+        assert_eq!(
+            poe.verify_claim(&"Hello, world!", &"alice", 1),
+            Err(VerifyError::ClaimedTooLate)
+        );
+        assert_eq!(poe.verify_claim(&"Hello, world!", &"alice", 2), Ok(()));
+        assert_eq!(poe.verify_claim(&"Hello, world!", &"bob", 2), Err(VerifyError::WrongOwner));
+
+        // The new owner of a transferred claim only owns it from the block of the transfer.
+        poe.on_block_start(3, None);
+        assert_eq!(poe.transfer_claim("alice", "Hello, world!", "bob"), Ok(()));
+        assert_eq!(poe.get_claim(&"Hello, world!"), Some(&("bob", 2)));
+        assert_eq!(
+            poe.verify_claim(&"Hello, world!", &"bob", 2),
+            Err(VerifyError::ClaimedTooLate)
+        );
+        assert_eq!(poe.verify_claim(&"Hello, world!", &"bob", 3), Ok(()));
+        assert_eq!(poe.verify_claim(&"Hello, world!", &"alice", 3), Err(VerifyError::WrongOwner));
+        assert_eq!(poe.try_state(3), Ok(()));
+    }
+
+    #[test]
+    fn content_len() {
+        let mut poe = super::Pallet::<TextConfig>::default();
+        let mut currency = MockCurrency::default();
+        let max = <TextConfig as super::Config>::MAX_CONTENT_LEN;
+
+        assert_eq!(poe.create_claim("alice", &mut currency, vec![]), Err(PoeError::EmptyContent));
+        assert_eq!(
+            poe.create_claim("alice", &mut currency, vec![b'a'; max + 1]),
+            Err(PoeError::ContentTooLarge)
+        );
+        assert_eq!(
+            poe.create_claims("alice", &mut currency, vec![b"b".to_vec(), vec![b'a'; max + 1]]),
+            Err(PoeError::ContentTooLarge)
+        );
+        assert_eq!(poe.claim_count(), 0);
+        assert_eq!(poe.create_claim("alice", &mut currency, vec![b'a'; max]), Ok(()));
 
+        // The limits apply to the normalized content, which is what gets hashed.
+        assert_eq!(
+            poe.create_claim("alice", &mut currency, b" \n\t ".to_vec()),
+            Err(PoeError::EmptyContent)
+        );
+        assert_eq!(
+            poe.create_claims("alice", &mut currency, vec![b"b".to_vec(), b"   ".to_vec()]),
+            Err(PoeError::EmptyContent)
+        );
+        let mut padded = vec![b' '; max];
+        padded.push(b'b');
+        assert_eq!(poe.create_claim("alice", &mut currency, padded), Ok(()));
+        assert_eq!(poe.claim_count(), 2);
+
+        assert_eq!(super::ContentLen::len_bytes(&"héllo"), 6);
+        assert_eq!(super::ContentLen::len_bytes(&[0u8; 32]), 32);
+    }
+
+    #[test]
+    fn claim_metadata() {
+        let mut poe = super::Pallet::<DepositConfig>::default();
+        let mut currency = MockCurrency::default();
+        currency.balances.insert("alice", 100);
+        assert_eq!(poe.create_claim("alice", &mut currency, "a"), Ok(()));
+
+        // Only the owner attaches metadata, of at most `MAX_METADATA_LEN` bytes.
+        assert_eq!(poe.set_claim_metadata("bob", "a", b"title".to_vec()), Err(PoeError::NotOwner));
+        assert_eq!(
+            poe.set_claim_metadata("alice", "x", b"title".to_vec()),
+            Err(PoeError::ClaimNotFound)
+        );
+        assert_eq!(
+            poe.set_claim_metadata("alice", "a", b"too long!".to_vec()),
+            Err(PoeError::MetadataTooLong)
+        );
+        assert_eq!(poe.get_claim_metadata(&"a"), None);
+        assert_eq!(poe.set_claim_metadata("alice", "a", b"12345678".to_vec()), Ok(()));
+        assert_eq!(poe.set_claim_metadata("alice", "a", b"title".to_vec()), Ok(()));
+        assert_eq!(poe.get_claim_metadata(&"a"), Some(&b"title"[..]));
+
+        // The metadata is kept when the claim is transferred, and only the new owner manages it.
+        assert_eq!(poe.transfer_claim("alice", "a", "bob"), Ok(()));
+        assert_eq!(poe.get_claim_metadata(&"a"), Some(&b"title"[..]));
+        assert_eq!(poe.clear_claim_metadata("alice", "a"), Err(PoeError::NotOwner));
+        assert_eq!(poe.clear_claim_metadata("bob", "a"), Ok(()));
+        assert_eq!(poe.get_claim_metadata(&"a"), None);
+
+        // The metadata is removed with the claim, so it does not describe a later claim.
+        assert_eq!(poe.set_claim_metadata("bob", "a", b"uri".to_vec()), Ok(()));
+        assert_eq!(poe.revoke_claim("bob", &mut currency, "a"), Ok(()));
+        assert_eq!(poe.get_claim_metadata(&"a"), None);
+        assert_eq!(poe.try_state(0), Ok(()));
+        assert_eq!(poe.create_claim("alice", &mut currency, "a"), Ok(()));
+        assert_eq!(poe.get_claim_metadata(&"a"), None);
+    }
+
+    #[test]
+    fn check_config() {
+        assert!(super::Pallet::<DepositConfig>::check_config().is_empty());
+        assert_eq!(
+            super::Pallet::<TestConfig>::check_config(),
+            vec![(ConfigSeverity::Warning, "claim TTL is zero, claims never expire")]
+        );
+        assert_eq!(
+            super::Pallet::<BaseConfig<&'static str, super::Identity, 10, 5, 0>>::check_config(),
+            vec![(ConfigSeverity::Error, "batch limit is zero, `create_claims` always fails")]
+        );
+    }
+}
@@ -1,6 +1,9 @@
 // The support module helps bring in various types and traits. 
 // The traits will be used to enhance our simple state machine.
 
+use core::fmt::Debug;
+use num::traits::Zero;
+
 // The two components of a block are the header and the extrinsic.
 pub struct Block<Header, Extrinsic> {
 	pub header: Header,
@@ -16,6 +19,9 @@ pub struct Header<BlockNumber> {
 pub struct Extrinsic<Caller, Call> {
 	pub caller: Caller,
 	pub call: Call,
+	// An optional key chosen by the client. The same caller can not use the same key twice within
+	// the idempotency window, which catches retried submissions of the same extrinsic.
+	pub idempotency_key: Option<[u8; 16]>,
 }
 
 // Shows the reults of the calls to those specific functions. 
@@ -28,3 +34,160 @@ pub trait Dispatch {
 	type Call;
 	fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> DispatchResult;
 }
+
+// A value provided by the runtime to a pallet, for values which can not be constants, e.g. accounts.
+pub trait Get<T> {
+	fn get() -> T;
+}
+
+// `()` provides no value.
+impl<T> Get<Option<T>> for () {
+	fn get() -> Option<T> {
+		None
+	}
+}
+
+// Charges the fee of an extrinsic before its call is dispatched. An extrinsic whose caller can not
+// pay is rejected, while the fee of a failing call is still kept.
+pub trait ChargeFee<Caller> {
+	fn charge_fee(&mut self, who: &Caller) -> DispatchResult;
+}
+
+// Work done by each pallet when a new block starts, before its extrinsics are executed.
+// The `author` is the block author recorded in the system pallet, if one is set.
+pub trait OnBlockStart<BlockNumber, AccountId> {
+	fn on_block_start(&mut self, block_number: BlockNumber, author: Option<&AccountId>);
+}
+
+// Undo support for the storage of a pallet. The runtime starts a transaction in every pallet
+// before dispatching a call, and rolls them back if the call fails, so a call failing midway
+// leaves no partial state. Transactions nest, so calls can use them too.
+pub trait Transactional {
+	fn start_transaction(&mut self);
+	fn commit_transaction(&mut self);
+	fn rollback_transaction(&mut self);
+}
+
+// Dispatch for pallets whose calls use the currency of the runtime, e.g. to take a deposit. The
+// runtime passes the pallet named by `#[currency(...)]` on the field of the dispatching pallet.
+pub trait DispatchWithCurrency<Currency> {
+	type Caller;
+	type Call;
+	fn dispatch(&mut self, currency: &mut Currency, caller: Self::Caller, call: Self::Call) -> DispatchResult;
+}
+
+// Access to the funds of accounts, so pallets can charge or reward accounts without depending on
+// the balances pallet itself.
+pub trait Currency<AccountId> {
+	type Balance: Copy + Zero + Debug;
+	// The free balance of `who`.
+	fn free_balance(&self, who: &AccountId) -> Self::Balance;
+	// Move `amount` from `from` to `to`.
+	fn transfer(&mut self, from: &AccountId, to: &AccountId, amount: Self::Balance) -> DispatchResult;
+	// Take `amount` out of the free balance of `who`, removing it from the issuance.
+	fn withdraw(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+	// Add `amount` to the free balance of `who`, adding it to the issuance.
+	fn deposit(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+	// Set `amount` of the free balance of `who` aside, so it can not be spent until it is unreserved.
+	fn reserve(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+	// Move up to `amount` of the reserved balance of `who` back to its free balance. Returns how
+	// much was unreserved.
+	fn unreserve(&mut self, who: &AccountId, amount: Self::Balance) -> Self::Balance;
+	// Move `amount` of the reserved balance of `from` to the free balance of `to`, without the
+	// checks of a transfer out of `from`.
+	fn repatriate_reserved(&mut self, from: &AccountId, to: &AccountId, amount: Self::Balance) -> DispatchResult;
+}
+
+// Integrity checks owned by each pallet. The runtime runs all of them with `try_state_all`.
+// An error is the description of the violated invariant.
+pub trait TryState<BlockNumber> {
+	fn try_state(&self, block_number: BlockNumber) -> Result<(), &'static str>;
+}
+
+// How serious a configuration problem is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigSeverity {
+	Warning, // A surprising choice, which the runtime still starts with.
+	Error,   // A broken choice, which the runtime refuses to start with.
+}
+
+// Configuration checks owned by each pallet. The runtime gathers them in `validate_configuration`.
+// Each message describes an inconsistent choice in the pallet `Config`.
+pub trait CheckConfig {
+	fn check_config() -> Vec<(ConfigSeverity, &'static str)>;
+}
+
+// Events raised by each pallet. Pallets buffer their own events, and the runtime moves them into
+// the system pallet after each extrinsic, wrapped in its `RuntimeEvent`.
+pub trait TakeEvents {
+	type Event;
+	fn take_events(&mut self) -> Vec<Self::Event>;
+}
+
+// A configuration problem found in a pallet of the runtime.
+#[derive(Debug, PartialEq)]
+pub struct ConfigWarning {
+	pub pallet: &'static str,
+	pub severity: ConfigSeverity,
+	pub message: &'static str,
+}
+
+// Log every configuration problem of a runtime, and fail with its errors if there are any.
+pub fn check_configuration(warnings: Vec<ConfigWarning>) -> Result<(), Vec<ConfigWarning>> {
+	for warning in &warnings {
+		let kind = match warning.severity {
+			ConfigSeverity::Warning => "Warning",
+			ConfigSeverity::Error => "Error",
+		};
+		eprintln!("Configuration {kind}\n\tPallet: {}\n\t{kind}: {}", warning.pallet, warning.message);
+	}
+	let errors = warnings
+		.into_iter()
+		.filter(|warning| warning.severity == ConfigSeverity::Error)
+		.collect::<Vec<_>>();
+	if errors.is_empty() {
+		Ok(())
+	} else {
+		Err(errors)
+	}
+}
+
+// Hook for pallets which want to react to successful transfers, without the balances pallet
+// knowing about them. Failed transfers never call this. The balances pallet owns the listener, so
+// it can keep its own state.
+pub trait OnTransfer<AccountId, Balance> {
+	fn on_transfer(&mut self, from: &AccountId, to: &AccountId, amount: &Balance);
+}
+
+// Hook for pallets which want to react to the new free balance of an account.
+pub trait OnBalanceChanged<AccountId, Balance> {
+	fn on_balance_changed(&mut self, who: &AccountId, new_free: &Balance);
+}
+
+// The empty tuple is the "no listeners" hook, and a tuple of hooks calls each of them in order.
+// This lets the runtime register several listeners at once, e.g. `type TransferHooks = (A, B);`.
+macro_rules! impl_hooks_for_tuples {
+	($( ( $($hook:ident $index:tt),* ) ),*) => {
+		$(
+			impl<AccountId, Balance, $($hook: OnTransfer<AccountId, Balance>),*>
+				OnTransfer<AccountId, Balance> for ( $($hook,)* )
+			{
+				#[allow(unused_variables)]
+				fn on_transfer(&mut self, from: &AccountId, to: &AccountId, amount: &Balance) {
+					$( self.$index.on_transfer(from, to, amount); )*
+				}
+			}
+
+			impl<AccountId, Balance, $($hook: OnBalanceChanged<AccountId, Balance>),*>
+				OnBalanceChanged<AccountId, Balance> for ( $($hook,)* )
+			{
+				#[allow(unused_variables)]
+				fn on_balance_changed(&mut self, who: &AccountId, new_free: &Balance) {
+					$( self.$index.on_balance_changed(who, new_free); )*
+				}
+			}
+		)*
+	};
+}
+
+impl_hooks_for_tuples!((), (A 0), (A 0, B 1), (A 0, B 1, C 2), (A 0, B 1, C 2, D 3));
@@ -4,14 +4,19 @@
 
 use core::ops::AddAssign;
 use num::traits::{One, Zero};
+use crate::support::{CheckConfig, ConfigSeverity, DispatchResult, TryState};
 use std::collections::BTreeMap; // Used to map user addresses to balances.
 
 
 // Here you are making these types configurable in the future. 
 pub trait Config {
 	type AccountId: Ord + Clone;
-	type BlockNumber: Zero + One + AddAssign + Copy;
+	type BlockNumber: Zero + One + AddAssign + Copy + PartialOrd;
 	type Nonce: Zero + One + Copy;
+	// The number of blocks during which an idempotency key can not be reused by the same caller.
+	const IDEMPOTENCY_WINDOW: Self::BlockNumber;
+	// The events of every pallet, as collected by the runtime.
+	type RuntimeEvent;
 }
 // This is the System Pallet.
 // It handles low level state needed for your blockchain.
@@ -19,6 +24,9 @@ pub trait Config {
 pub struct Pallet<T: Config> {               // "T: Config" is used to make the pallet configurable and scalable.  
     block_number: T::BlockNumber,            // The current block number.
     nonce: BTreeMap<T::AccountId, T::Nonce>, // A map from an account to their nonce
+    idempotency_keys: BTreeMap<(T::AccountId, [u8; 16]), T::BlockNumber>, // The block at which each recent (caller, key) pair was seen.
+    events: Vec<T::RuntimeEvent>,            // The events deposited during the current block.
+    block_author: Option<T::AccountId>,      // The account which authors the blocks, and receives the tips.
 }
 
 // Here you are implementing the Pallet and specifying you want it to be configurable
@@ -26,7 +34,13 @@ impl<T: Config> Pallet<T> {
 	
 	// Initiating a new instance. 
 	pub fn new() -> Self {
-		Self { block_number: T::BlockNumber::zero(), nonce: BTreeMap::new() }
+		Self {
+			block_number: T::BlockNumber::zero(),
+			nonce: BTreeMap::new(),
+			idempotency_keys: BTreeMap::new(),
+			events: Vec::new(),
+			block_author: None,
+		}
 	}
 
 	// Fist block number. 
@@ -35,25 +49,115 @@ impl<T: Config> Pallet<T> {
 	}
 	
 	// Increase in block numbers. 
+	// Events are kept per block, so the events of the previous block are cleared.
 	pub fn inc_block_number(&mut self) {
 		self.block_number += T::BlockNumber::one();
+		self.events.clear();
+
+		// Forget the idempotency keys whose window has passed, so they can be used again.
+		let block_number = self.block_number;
+		self.idempotency_keys.retain(|_, seen| {
+			let mut expiry = *seen;
+			expiry += T::IDEMPOTENCY_WINDOW;
+			expiry > block_number
+		});
+	}
+
+	// Record an event which happened in the current block.
+	pub fn deposit_event(&mut self, event: T::RuntimeEvent) {
+		self.events.push(event);
+	}
+
+	// Get the events of the current block, in the order they happened.
+	pub fn events(&self) -> &[T::RuntimeEvent] {
+		&self.events
+	}
+
+	// Set the account which authors the next blocks, or `None` if there is no author.
+	// The pallets are told about the author when a block starts.
+	pub fn set_block_author(&mut self, author: Option<T::AccountId>) {
+		self.block_author = author;
+	}
+
+	// Get the block author, if one is set.
+	pub fn block_author(&self) -> Option<&T::AccountId> {
+		self.block_author.as_ref()
+	}
+
+	// Check that `who` did not use the idempotency `key` within the idempotency window, without
+	// recording it.
+	pub fn check_idempotency_key(&self, who: &T::AccountId, key: [u8; 16]) -> DispatchResult {
+		if self.idempotency_keys.contains_key(&(who.clone(), key)) {
+			return Err("duplicate idempotency key");
+		}
+		Ok(())
+	}
+
+	// Record the idempotency `key` of an extrinsic sent by `who`.
+	// Fails if `who` already used the same key within the idempotency window.
+	pub fn note_idempotency_key(&mut self, who: &T::AccountId, key: [u8; 16]) -> DispatchResult {
+		self.check_idempotency_key(who, key)?;
+		self.idempotency_keys.insert((who.clone(), key), self.block_number);
+		Ok(())
+	}
+
+	// Get the nonce of an account `who`. Accounts which never sent an extrinsic have a zero nonce.
+	pub fn nonce(&self, who: &T::AccountId) -> T::Nonce {
+		*self.nonce.get(who).unwrap_or(&T::Nonce::zero())
 	}
 
 	// Increase nonce.
 	pub fn inc_nonce(&mut self, who: &T::AccountId) {
-		let nonce = *self.nonce.get(&who).unwrap_or(&T::Nonce::zero());
+		let nonce = self.nonce(who);
 		let new_nonce = nonce + T::Nonce::one();
 		self.nonce.insert(who.clone(), new_nonce);
 	}
 }
 
+// A default pallet is an empty one, as created by `new`.
+impl<T: Config> Default for Pallet<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// The integrity checks of the system pallet.
+impl<T: Config> TryState<T::BlockNumber> for Pallet<T> {
+	fn try_state(&self, _block_number: T::BlockNumber) -> Result<(), &'static str> {
+		// Nonces are only stored once they are incremented, so they are never zero.
+		if self.nonce.values().any(|nonce| nonce.is_zero()) {
+			return Err("zero nonce stored");
+		}
+		Ok(())
+	}
+}
+
+// The configuration checks of the system pallet.
+impl<T: Config> CheckConfig for Pallet<T> {
+	fn check_config() -> Vec<(ConfigSeverity, &'static str)> {
+		let mut warnings = vec![];
+		if T::IDEMPOTENCY_WINDOW.is_zero() {
+			warnings.push((
+				ConfigSeverity::Warning,
+				"idempotency window is zero, duplicate keys are never rejected",
+			));
+		}
+		warnings
+	}
+}
+
 #[cfg(test)]
 mod test {
-	struct TestConfig;
-	impl super::Config for TestConfig {
+	use crate::support::{CheckConfig, ConfigSeverity};
+
+	// The config of every test, where the idempotency window can be overridden.
+	struct TestConfig<const IDEMPOTENCY_WINDOW: u32 = 3>;
+	impl<const IDEMPOTENCY_WINDOW: u32> super::Config for TestConfig<IDEMPOTENCY_WINDOW> {
 		type AccountId = String;
 		type BlockNumber = u32;
 		type Nonce = u32;
+		const IDEMPOTENCY_WINDOW: u32 = IDEMPOTENCY_WINDOW;
+		type RuntimeEvent = &'static str;
 	}
 
 	#[test]
@@ -63,9 +167,47 @@ mod test {
 		system.inc_nonce(&"alice".to_string()); // Increment nonce for 'alice'.
 
 		assert_eq!(system.block_number(), 1); // Assert block number is incremented to 1
-		assert_eq!(system.nonce.get(&"alice".to_string()), Some(&1)); // Assert nonce for 'alice' is correctly set and incremented to 1.
-		assert_eq!(system.nonce.get(&"bob".to_string()), None); // Assert nonce for 'bob' is `None` since it has not been initialized.
+		assert_eq!(system.nonce.get("alice"), Some(&1)); // Assert nonce for 'alice' is correctly set and incremented to 1.
+		assert_eq!(system.nonce.get("bob"), None); // Assert nonce for 'bob' is `None` since it has not been initialized.
 	}
-}
 
+	#[test]
+	fn idempotency_keys() {
+		let mut system = super::Pallet::<TestConfig>::new();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let key = [1; 16];
+
+		system.inc_block_number(); // Block 1.
+		assert_eq!(system.note_idempotency_key(&alice, key), Ok(()));
+		assert_eq!(system.note_idempotency_key(&alice, key), Err("duplicate idempotency key")); // Same caller and key.
+		assert_eq!(system.note_idempotency_key(&bob, key), Ok(())); // Another caller may use the same key.
+
+		system.inc_block_number(); // Block 2.
+		system.inc_block_number(); // Block 3, the last block of the window.
+		assert_eq!(system.note_idempotency_key(&alice, key), Err("duplicate idempotency key"));
 
+		system.inc_block_number(); // Block 4, the window has passed.
+		assert_eq!(system.note_idempotency_key(&alice, key), Ok(())); // The key can be reused.
+	}
+
+	#[test]
+	fn events() {
+		let mut system = super::Pallet::<TestConfig>::new();
+		system.inc_block_number(); // Block 1.
+		system.deposit_event("first");
+		system.deposit_event("second");
+		assert_eq!(system.events(), &["first", "second"]);
+
+		system.inc_block_number(); // Block 2 starts without the events of block 1.
+		assert!(system.events().is_empty());
+	}
+
+	#[test]
+	fn check_config() {
+		assert!(super::Pallet::<TestConfig>::check_config().is_empty());
+		assert_eq!(
+			super::Pallet::<TestConfig<0>>::check_config(),
+			vec![(ConfigSeverity::Warning, "idempotency window is zero, duplicate keys are never rejected")]
+		);
+	}
+}